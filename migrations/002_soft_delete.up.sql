@@ -0,0 +1,6 @@
+-- Soft delete: rows are moved to the trash and purged after the retention period
+ALTER TABLE properties ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE feedback ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_properties_deleted_at ON properties (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_feedback_deleted_at ON feedback (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Audit log of changes made to tenant data
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    actor_user_id TEXT NOT NULL,
+    entity VARCHAR(50) NOT NULL,
+    entity_id INTEGER NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    changes JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_audit_log_tenant_created_at ON audit_log (tenant_id, created_at DESC);
+CREATE INDEX idx_audit_log_entity ON audit_log (tenant_id, entity, entity_id);
@@ -0,0 +1,7 @@
+-- Per-tenant translation of listing values into what syndication portals expect
+CREATE TABLE feed_mappings (
+    tenant_id INTEGER PRIMARY KEY REFERENCES tenants(id) ON DELETE CASCADE,
+    property_type JSONB NOT NULL DEFAULT '{}',
+    currency JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
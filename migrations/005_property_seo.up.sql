@@ -0,0 +1,16 @@
+-- SEO: stable per-tenant slugs and optional overrides of the generated metadata
+ALTER TABLE properties ADD COLUMN slug VARCHAR(255);
+ALTER TABLE properties ADD COLUMN meta_title VARCHAR(70);
+ALTER TABLE properties ADD COLUMN meta_description VARCHAR(160);
+ALTER TABLE properties ADD COLUMN og_image TEXT;
+
+-- Existing listings get their id appended so the backfilled slugs cannot collide
+UPDATE properties
+SET slug = COALESCE(NULLIF(trim(both '-' from regexp_replace(
+        translate(lower(title), 'áàäâéèëêíìïîóòöôúùüûñç', 'aaaaeeeeiiiioooouuuunc'),
+        '[^a-z0-9]+', '-', 'g'
+    )), ''), 'property') || '-' || id;
+
+ALTER TABLE properties ALTER COLUMN slug SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_properties_tenant_slug ON properties (tenant_id, slug);
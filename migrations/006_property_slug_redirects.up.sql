@@ -0,0 +1,10 @@
+-- Former slugs of a property, so old links keep resolving after it is renamed
+CREATE TABLE property_slug_redirects (
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    slug VARCHAR(255) NOT NULL,
+    property_id INTEGER NOT NULL REFERENCES properties(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tenant_id, slug)
+);
+
+CREATE INDEX idx_property_slug_redirects_property_id ON property_slug_redirects (property_id);
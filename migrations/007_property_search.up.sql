@@ -0,0 +1,25 @@
+-- Full-text search over listings with Spanish stemming
+ALTER TABLE properties ADD COLUMN search_vector tsvector;
+
+CREATE FUNCTION properties_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('spanish', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('spanish', concat_ws(' ', NEW.city, NEW.address)), 'B') ||
+        setweight(to_tsvector('spanish', coalesce(array_to_string(NEW.amenities, ' '), '')), 'B') ||
+        setweight(to_tsvector('spanish', coalesce(NEW.description, '')), 'C');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER properties_search_vector_trigger
+    BEFORE INSERT OR UPDATE OF title, description, address, city, amenities ON properties
+    FOR EACH ROW EXECUTE FUNCTION properties_search_vector_update();
+
+-- Fires the trigger for the existing listings, without touching updated_at:
+-- it backs their ETags and sitemap lastmod
+ALTER TABLE properties DISABLE TRIGGER set_timestamps_properties;
+UPDATE properties SET title = title;
+ALTER TABLE properties ENABLE TRIGGER set_timestamps_properties;
+
+CREATE INDEX idx_properties_search_vector ON properties USING GIN (search_vector);
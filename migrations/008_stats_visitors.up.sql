@@ -0,0 +1,18 @@
+-- Pseudonymous visitor behind each event: a hash of the visitor's id and a
+-- salt that changes daily, so visitors cannot be followed across days
+ALTER TABLE stats ADD COLUMN visitor_id VARCHAR(64);
+
+CREATE INDEX idx_stats_tenant_visitor ON stats (tenant_id, visitor_id, created_at)
+    WHERE visitor_id IS NOT NULL;
+
+-- Only the current day's salt is kept; older ones are deleted as soon as a
+-- new one is created
+CREATE TABLE stats_salts (
+    day DATE PRIMARY KEY,
+    salt VARCHAR(64) NOT NULL
+);
+
+-- Raw IP addresses are no longer stored
+UPDATE stats
+SET details = details #- '{metadata,ip_address}'
+WHERE details->'metadata' ? 'ip_address';
@@ -0,0 +1,5 @@
+-- Flag past events from known bots, matching the signatures in
+-- src/utils/user_agent.rs, so reports can leave them out
+UPDATE stats
+SET details = jsonb_set(details, '{metadata,bot}', '"signature"')
+WHERE details->'metadata'->>'user_agent' ~* '(bot|crawl|spider|slurp|mediapartners|facebookexternalhit|embedly|preview|whatsapp|pingdom|uptimerobot|statuscake|site24x7|newrelicpinger|datadog|headlesschrome|phantomjs|lighthouse|curl/|wget/|python-requests|python-urllib|go-http-client|java/|okhttp|axios/|node-fetch|postmanruntime|scrapy|semrush|ahrefs|mj12)';
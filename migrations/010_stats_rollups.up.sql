@@ -0,0 +1,32 @@
+-- Daily event counts per UTC day, kept after raw events are purged
+CREATE TABLE stats_daily (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    property_id INTEGER NOT NULL DEFAULT 0,  -- 0 when the events refer to no listing
+    referrer VARCHAR(500) NOT NULL DEFAULT '',
+    dimension VARCHAR(255) NOT NULL DEFAULT '',  -- value the event type is broken down by
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, property_id, referrer, dimension, is_bot)
+);
+
+-- Totals per event type, whose visitors cannot be added up from stats_daily
+CREATE TABLE stats_daily_totals (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, is_bot)
+);
+
+-- Last day rolled up; later events are read from stats
+CREATE TABLE stats_rollup_state (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    rolled_up_to DATE
+);
+
+INSERT INTO stats_rollup_state (id, rolled_up_to) VALUES (TRUE, NULL);
@@ -0,0 +1,21 @@
+-- Daily event counts per traffic source, medium and campaign
+CREATE TABLE stats_daily_sources (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    source VARCHAR(100) NOT NULL,
+    medium VARCHAR(100) NOT NULL,
+    campaign VARCHAR(100) NOT NULL DEFAULT '',  -- '' when the visit came from no campaign
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, source, medium, campaign, is_bot)
+);
+
+-- Days already rolled up only have events recorded before attribution, which
+-- are kept apart as not set. They are filled from the per type totals rather
+-- than from raw events, which may already be purged, so both keep agreeing
+INSERT INTO stats_daily_sources (day, tenant_id, event_type, source, medium, is_bot, events, visitors)
+SELECT day, tenant_id, event_type, '(not set)', '(not set)', is_bot, events, visitors
+FROM stats_daily_totals
+WHERE day <= (SELECT rolled_up_to FROM stats_rollup_state);
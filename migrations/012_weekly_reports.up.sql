@@ -0,0 +1,16 @@
+-- Email preferences per tenant; tenants without a row get the weekly report
+CREATE TABLE report_preferences (
+    tenant_id INTEGER PRIMARY KEY REFERENCES tenants(id) ON DELETE CASCADE,
+    weekly_report BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Lets the unsubscribe link in the emails work without signing in
+    unsubscribe_token VARCHAR(64) NOT NULL UNIQUE DEFAULT gen_random_uuid()::text,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Weeks already reported to each tenant, so restarts do not send twice
+CREATE TABLE weekly_reports_sent (
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    week_start DATE NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tenant_id, week_start)
+);
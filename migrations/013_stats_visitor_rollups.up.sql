@@ -0,0 +1,29 @@
+-- Visitors only add up across days: the rows of stats_daily are split by
+-- listing, referrer and dimension, so a visitor spread over several of them
+-- would be counted once per row. Each breakdown reporting visitors gets its own
+-- rollup instead.
+
+-- Per value the event type is broken down by
+CREATE TABLE stats_daily_dimensions (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    dimension VARCHAR(255) NOT NULL DEFAULT '',
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, dimension, is_bot)
+);
+
+-- Days after the first one still holding raw events are rolled up again from
+-- them, filling the new tables exactly
+UPDATE stats_rollup_state
+SET rolled_up_to = LEAST(rolled_up_to, (SELECT (MIN(created_at) AT TIME ZONE 'UTC')::date FROM stats))
+WHERE rolled_up_to IS NOT NULL;
+
+-- Earlier days can only be filled from stats_daily, overcounting their visitors
+INSERT INTO stats_daily_dimensions (day, tenant_id, event_type, dimension, is_bot, events, visitors)
+SELECT day, tenant_id, event_type, dimension, is_bot, SUM(events), SUM(visitors)
+FROM stats_daily
+WHERE day <= (SELECT rolled_up_to FROM stats_rollup_state)
+GROUP BY day, tenant_id, event_type, dimension, is_bot;
@@ -0,0 +1,46 @@
+-- Per listing, for its totals and the daily export, whose visitors cannot be
+-- added up from stats_daily either
+CREATE TABLE stats_daily_properties (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    property_id INTEGER NOT NULL DEFAULT 0,  -- 0 when the events refer to no listing
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, property_id, is_bot)
+);
+
+-- Per listing and referring host, without www.
+CREATE TABLE stats_daily_referrers (
+    day DATE NOT NULL,
+    tenant_id INTEGER NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    property_id INTEGER NOT NULL DEFAULT 0,
+    referrer_host VARCHAR(255) NOT NULL DEFAULT '',  -- '' when there was no referrer
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE,
+    events BIGINT NOT NULL,
+    visitors BIGINT NOT NULL,
+    PRIMARY KEY (tenant_id, day, event_type, property_id, referrer_host, is_bot)
+);
+
+-- Days after the first one still holding raw events are rolled up again from
+-- them, filling the new tables exactly
+UPDATE stats_rollup_state
+SET rolled_up_to = LEAST(rolled_up_to, (SELECT (MIN(created_at) AT TIME ZONE 'UTC')::date FROM stats))
+WHERE rolled_up_to IS NOT NULL;
+
+-- Earlier days can only be filled from stats_daily, overcounting their visitors
+INSERT INTO stats_daily_properties (day, tenant_id, event_type, property_id, is_bot, events, visitors)
+SELECT day, tenant_id, event_type, property_id, is_bot, SUM(events), SUM(visitors)
+FROM stats_daily
+WHERE day <= (SELECT rolled_up_to FROM stats_rollup_state)
+GROUP BY day, tenant_id, event_type, property_id, is_bot;
+
+INSERT INTO stats_daily_referrers (day, tenant_id, event_type, property_id, referrer_host, is_bot, events, visitors)
+SELECT day, tenant_id, event_type, property_id,
+       COALESCE(LEFT(LOWER(SUBSTRING(referrer FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://(?:www\.)?([^/:?#]+)')), 255), ''),
+       is_bot, SUM(events), SUM(visitors)
+FROM stats_daily
+WHERE day <= (SELECT rolled_up_to FROM stats_rollup_state)
+GROUP BY 1, 2, 3, 4, 5, 6;
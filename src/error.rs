@@ -1,9 +1,15 @@
-use actix_web::{HttpResponse, ResponseError};
-use serde_json::Error as SerdeError;
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::{header, StatusCode},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Serialize;
+use serde_json::{json, Error as SerdeError, Value as JsonValue};
 use sqlx::Error as SqlxError;
 use thiserror::Error;
+use validator::ValidationErrors;
 
-use crate::utils::lucia;
+use crate::utils::{lucia, request_id};
 
 #[derive(Error, Debug)]
 pub enum ApiError {
@@ -22,6 +28,12 @@ pub enum ApiError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Invalid {0}: {1}")]
+    InvalidInput(&'static str, String),
+
+    #[error("Validation failed: {0}")]
+    ValidationError(#[from] ValidationErrors),
+
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
@@ -31,82 +43,194 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Precondition required: {0}")]
+    PreconditionRequired(String),
+
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
 
+    #[error("Plan limit exceeded: {0}")]
+    PlanLimitExceeded(String),
+
+    #[error("Too many requests, retry in {0}s")]
+    RateLimited(u64),
+
     #[error("Lucia error: {0}")]
     LuciaError(#[from] lucia::Error),
 }
 
+/// Body returned for every failed request.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<JsonValue>,
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    /// Stable, machine-readable identifier of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::ParseError(_) => "parse_error",
+            ApiError::UnexpectedError(_) => "unexpected_error",
+            ApiError::DatabaseError(_) => "database_error",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::InvalidInput(_, _) => "invalid_input",
+            ApiError::ValidationError(_) => "validation_failed",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::PreconditionFailed(_) => "precondition_failed",
+            ApiError::PreconditionRequired(_) => "precondition_required",
+            ApiError::ServiceUnavailable(_) => "service_unavailable",
+            ApiError::PlanLimitExceeded(_) => "plan_limit_exceeded",
+            ApiError::RateLimited(_) => "rate_limited",
+            ApiError::LuciaError(lucia_error) => lucia_code(lucia_error),
+        }
+    }
+
+    fn details(&self) -> Option<JsonValue> {
+        match self {
+            ApiError::InvalidInput(source, _) => Some(json!({ "source": source })),
+            ApiError::PlanLimitExceeded(limit) => Some(json!({ "limit": limit })),
+            ApiError::RateLimited(retry_after) => Some(json!({ "retry_after": retry_after })),
+            ApiError::ValidationError(errors) => Some(validation_details(errors)),
+            _ => None,
+        }
+    }
+}
+
 impl ResponseError for ApiError {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            ApiError::ParseError(_) => HttpResponse::InternalServerError().body(self.to_string()),
-            ApiError::UnexpectedError(_) => {
-                HttpResponse::InternalServerError().json(self.to_string())
-            }
-            ApiError::DatabaseError(_) => {
-                HttpResponse::InternalServerError().json(self.to_string())
-            }
-            ApiError::NotFound(_) => HttpResponse::NotFound().json(self.to_string()),
-            ApiError::BadRequest(_) => HttpResponse::BadRequest().json(self.to_string()),
-            ApiError::Forbidden(_) => HttpResponse::Forbidden().json(self.to_string()),
-            ApiError::Unauthorized(_) => HttpResponse::Unauthorized().json(self.to_string()),
-            ApiError::Conflict(_) => HttpResponse::Conflict().json(self.to_string()),
-            ApiError::ServiceUnavailable(_) => {
-                HttpResponse::ServiceUnavailable().json(self.to_string())
-            }
-            ApiError::LuciaError(lucia_error) => match lucia_error {
-                lucia::Error::DatabaseConnectionError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::UserSessionNotFound => {
-                    HttpResponse::NotFound().json(self.to_string())
-                }
-                lucia::Error::InvalidSessionId => HttpResponse::BadRequest().json(self.to_string()),
-                lucia::Error::SessionExpired => HttpResponse::Unauthorized().json(self.to_string()),
-                lucia::Error::DatabaseQueryError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::UserSessionTableNotExist => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::AuthUserTableNotExist => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::InvalidCredentials => {
-                    HttpResponse::Unauthorized().json(self.to_string())
-                }
-                lucia::Error::DuplicateUserError(_) => {
-                    HttpResponse::Conflict().json(self.to_string())
-                }
-                lucia::Error::SessionCreationFailed => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::SessionDeletionFailed => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::UserCreationFailed => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::UserUpdateFailed => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::EncryptionError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::DecryptionError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::InvalidToken => HttpResponse::Unauthorized().json(self.to_string()),
-                lucia::Error::TokenExpired => HttpResponse::Unauthorized().json(self.to_string()),
-                lucia::Error::ConfigurationError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-                lucia::Error::UnexpectedError(_) => {
-                    HttpResponse::InternalServerError().json(self.to_string())
-                }
-            },
+            ApiError::ParseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::InvalidInput(_, _) => StatusCode::BAD_REQUEST,
+            ApiError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::PlanLimitExceeded(_) => StatusCode::PAYMENT_REQUIRED,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::LuciaError(lucia_error) => lucia_status_code(lucia_error),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let request_id = request_id::current();
+
+        // Internal failures are logged but never described to the client.
+        let message = if status.is_server_error() {
+            log::error!("[{}] {}", request_id.as_deref().unwrap_or("-"), self);
+            "Internal server error".to_string()
+        } else {
+            self.to_string()
+        };
+
+        let mut response = HttpResponse::build(status);
+        if let ApiError::RateLimited(retry_after) = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
         }
+
+        response.json(ErrorBody {
+            code: self.code(),
+            message,
+            details: self.details(),
+            request_id,
+        })
+    }
+}
+
+/// Flattens validator output into `{ field: [{ code, message }] }`.
+fn validation_details(errors: &ValidationErrors) -> JsonValue {
+    let fields: serde_json::Map<String, JsonValue> = errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            let errors = errors
+                .iter()
+                .map(|error| {
+                    json!({
+                        "code": error.code,
+                        "message": error.message,
+                    })
+                })
+                .collect();
+            (field.to_string(), JsonValue::Array(errors))
+        })
+        .collect();
+
+    json!({ "fields": fields })
+}
+
+fn lucia_code(error: &lucia::Error) -> &'static str {
+    match error {
+        lucia::Error::DatabaseConnectionError(_) => "auth_database_connection_error",
+        lucia::Error::UserSessionNotFound => "auth_session_not_found",
+        lucia::Error::InvalidSessionId => "auth_invalid_session_id",
+        lucia::Error::SessionExpired => "auth_session_expired",
+        lucia::Error::DatabaseQueryError(_) => "auth_database_query_error",
+        lucia::Error::UserSessionTableNotExist => "auth_session_table_missing",
+        lucia::Error::AuthUserTableNotExist => "auth_user_table_missing",
+        lucia::Error::InvalidCredentials => "auth_invalid_credentials",
+        lucia::Error::DuplicateUserError(_) => "auth_duplicate_user",
+        lucia::Error::SessionCreationFailed => "auth_session_creation_failed",
+        lucia::Error::SessionDeletionFailed => "auth_session_deletion_failed",
+        lucia::Error::UserCreationFailed => "auth_user_creation_failed",
+        lucia::Error::UserUpdateFailed => "auth_user_update_failed",
+        lucia::Error::EncryptionError(_) => "auth_encryption_error",
+        lucia::Error::DecryptionError(_) => "auth_decryption_error",
+        lucia::Error::InvalidToken => "auth_invalid_token",
+        lucia::Error::TokenExpired => "auth_token_expired",
+        lucia::Error::ConfigurationError(_) => "auth_configuration_error",
+        lucia::Error::UnexpectedError(_) => "auth_unexpected_error",
     }
 }
+
+fn lucia_status_code(error: &lucia::Error) -> StatusCode {
+    match error {
+        lucia::Error::DatabaseConnectionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::UserSessionNotFound => StatusCode::NOT_FOUND,
+        lucia::Error::InvalidSessionId => StatusCode::BAD_REQUEST,
+        lucia::Error::SessionExpired => StatusCode::UNAUTHORIZED,
+        lucia::Error::DatabaseQueryError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::UserSessionTableNotExist => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::AuthUserTableNotExist => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
+        lucia::Error::DuplicateUserError(_) => StatusCode::CONFLICT,
+        lucia::Error::SessionCreationFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::SessionDeletionFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::UserCreationFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::UserUpdateFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::EncryptionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::DecryptionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::InvalidToken => StatusCode::UNAUTHORIZED,
+        lucia::Error::TokenExpired => StatusCode::UNAUTHORIZED,
+        lucia::Error::ConfigurationError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        lucia::Error::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::InvalidInput("json", err.to_string()).into()
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::InvalidInput("path", err.to_string()).into()
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::InvalidInput("query", err.to_string()).into()
+}
@@ -0,0 +1,6 @@
+mod report;
+pub use report::*;
+mod stats;
+pub use stats::*;
+mod trash;
+pub use trash::*;
@@ -0,0 +1,23 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::modules::report;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically emails tenants the report of the last complete week. Each
+/// week is sent once, on the first run after it ends.
+pub fn spawn_weekly_reports(report_service: Arc<report::Service>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REPORT_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match report_service.send_weekly_reports().await {
+                Ok(sent) if sent > 0 => log::info!("Sent {} weekly reports", sent),
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to send weekly reports: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,29 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::modules::stats;
+
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically rolls up finished days of stats and purges raw events past
+/// the retention period.
+pub fn spawn_stats_rollup(stats_service: Arc<stats::Service>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ROLLUP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match stats_service.roll_up().await {
+                Ok(Some(day)) => log::info!("Rolled up stats up to {}", day),
+                Ok(None) => {}
+                Err(e) => log::error!("Failed to roll up stats: {}", e),
+            }
+
+            match stats_service.purge_events().await {
+                Ok(purged) if purged > 0 => log::info!("Purged {} raw stats events", purged),
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to purge raw stats events: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,35 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+
+use crate::modules::{front::landing::feedback, property};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically deletes trashed properties and feedback older than `retention_days`.
+pub fn spawn_trash_purge(
+    property_service: Arc<property::Service>,
+    feedback_service: Arc<feedback::Service>,
+    retention_days: i64,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let deleted_before = Utc::now() - chrono::Duration::days(retention_days);
+
+            match property_service.purge_trash(deleted_before).await {
+                Ok(purged) if purged > 0 => log::info!("Purged {} trashed properties", purged),
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to purge trashed properties: {}", e),
+            }
+
+            match feedback_service.purge_trash(deleted_before).await {
+                Ok(purged) if purged > 0 => log::info!("Purged {} trashed feedback", purged),
+                Ok(_) => {}
+                Err(e) => log::error!("Failed to purge trashed feedback: {}", e),
+            }
+        }
+    });
+}
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use actix_cors::Cors;
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use modules::{
+    audit, feed,
     front::{
         landing::{
             config::{self},
@@ -12,13 +13,14 @@ use modules::{
         },
         social_media,
     },
-    property, stats, tenant,
+    plan, property, report, site, stats, tenant,
 };
-use utils::s3;
+use utils::{client_ip::ClientIp, request_id, s3, Config};
 
 use crate::utils::database::PostgresRepository;
 
 mod error;
+mod jobs;
 mod modules;
 mod utils;
 
@@ -29,14 +31,84 @@ async fn main() -> std::io::Result<()> {
 
     let repo = Arc::new(PostgresRepository::new().await);
     let bukcet_service = Arc::new(s3::S3Repository::new().await.unwrap());
-    let property_service = Arc::new(property::Service::new(repo.clone(), bukcet_service.clone()));
-    let tenant_service = Arc::new(tenant::Service::new(repo.clone()));
-    let hero_service = Arc::new(hero::Service::new(repo.clone(), bukcet_service.clone()));
-    let config_service = Arc::new(config::Service::new(repo.clone(), bukcet_service.clone()));
-    let feedback_service = Arc::new(feedback::Service::new(repo.clone(), bukcet_service.clone()));
-    let stats_service = Arc::new(stats::Service::new(repo.clone()));
-    let social_service = Arc::new(social_media::Service::new(repo.clone()));
+    let audit_service = Arc::new(audit::Service::new(repo.clone()));
+    let plan_service = Arc::new(plan::Service::new(repo.clone()));
+    let feed_cache = Arc::new(feed::FeedCache::new());
+    let property_service = Arc::new(property::Service::new(
+        repo.clone(),
+        bukcet_service.clone(),
+        plan_service.clone(),
+        feed_cache.clone(),
+    ));
+    let tenant_service = Arc::new(tenant::Service::new(repo.clone(), audit_service.clone()));
+    let hero_service = Arc::new(hero::Service::new(
+        repo.clone(),
+        bukcet_service.clone(),
+        audit_service.clone(),
+    ));
+    let config_service = Arc::new(config::Service::new(
+        repo.clone(),
+        bukcet_service.clone(),
+        audit_service.clone(),
+    ));
+    let feedback_service = Arc::new(feedback::Service::new(
+        repo.clone(),
+        bukcet_service.clone(),
+        plan_service.clone(),
+        audit_service.clone(),
+    ));
+    let stats_service = Arc::new(stats::Service::new(
+        repo.clone(),
+        property_service.clone(),
+        Config::from_env().stats_retention_days,
+    ));
+    let social_service = Arc::new(social_media::Service::new(
+        repo.clone(),
+        audit_service.clone(),
+    ));
+    let feed_service = Arc::new(feed::Service::new(
+        repo.clone(),
+        property_service.clone(),
+        audit_service.clone(),
+        feed_cache.clone(),
+    ));
+    let config = Config::from_env();
+    let mailer: Arc<dyn report::port::Mailer> = match (config.mailer_url, config.mailer_from) {
+        (Some(url), Some(from)) => Arc::new(report::infrastructure::HttpMailer::new(
+            url,
+            config.mailer_api_key,
+            from,
+        )),
+        _ => {
+            log::warn!("MAILER_URL or MAILER_FROM not set, emails will only be logged");
+            Arc::new(report::infrastructure::LogMailer)
+        }
+    };
+    let report_service = Arc::new(report::Service::new(
+        repo.clone(),
+        mailer,
+        stats_service.clone(),
+        property_service.clone(),
+        config.public_api_url,
+    ));
+    let client_ip = Arc::new(ClientIp::new(config.trusted_proxies));
     let luci_service = Arc::new(utils::lucia::Service::new(repo.clone()));
+    let site_service = Arc::new(site::Service::new(
+        hero_service.clone(),
+        config_service.clone(),
+        social_service.clone(),
+        feedback_service.clone(),
+        property_service.clone(),
+        Config::from_env().site_url_template,
+    ));
+
+    jobs::spawn_trash_purge(
+        property_service.clone(),
+        feedback_service.clone(),
+        Config::from_env().trash_retention_days,
+    );
+    jobs::spawn_stats_rollup(stats_service.clone());
+    jobs::spawn_weekly_reports(report_service.clone());
 
     log::info!("Starting HTTP server on 0.0.0.0:80...");
     HttpServer::new(move || {
@@ -45,6 +117,10 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(cors)
             .wrap(Logger::default())
+            .wrap_fn(request_id::middleware)
+            .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+            .app_data(web::PathConfig::default().error_handler(error::path_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
             .service(
                 web::scope("/v2")
                     .configure(property::config)
@@ -53,9 +129,15 @@ async fn main() -> std::io::Result<()> {
                     .configure(feedback::config)
                     .configure(tenant::config)
                     .configure(stats::config)
-                    .configure(social_media::config),
+                    .configure(report::config)
+                    .configure(social_media::config)
+                    .configure(site::config)
+                    .configure(plan::config)
+                    .configure(audit::config)
+                    .configure(feed::config),
             )
             .app_data(web::Data::new(stats_service.clone()))
+            .app_data(web::Data::new(report_service.clone()))
             .app_data(web::Data::new(luci_service.clone()))
             .app_data(web::Data::new(property_service.clone()))
             .app_data(web::Data::new(social_service.clone()))
@@ -63,6 +145,11 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(config_service.clone()))
             .app_data(web::Data::new(feedback_service.clone()))
             .app_data(web::Data::new(hero_service.clone()))
+            .app_data(web::Data::new(site_service.clone()))
+            .app_data(web::Data::new(plan_service.clone()))
+            .app_data(web::Data::new(audit_service.clone()))
+            .app_data(web::Data::new(feed_service.clone()))
+            .app_data(web::Data::new(client_ip.clone()))
     })
     .bind("0.0.0.0:3000")?
     .run()
@@ -0,0 +1,34 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::sync::Arc;
+
+use crate::{
+    error::ApiError,
+    modules::{
+        audit::{AuditFilter, Service},
+        tenant,
+    },
+    utils::{database::Pagination, lucia},
+};
+
+pub async fn get_audit_log(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    pagination: web::Query<Pagination>,
+    filter: web::Query<AuditFilter>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let audit_log = service
+        .find_tenant_audit_log(tenant.id, filter.into_inner(), pagination.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(audit_log))
+}
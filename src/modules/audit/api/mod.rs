@@ -0,0 +1,8 @@
+use actix_web::web;
+use handler::get_audit_log;
+
+mod handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/audit").route("", web::get().to(get_audit_log)));
+}
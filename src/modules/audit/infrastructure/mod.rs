@@ -0,0 +1,2 @@
+mod pg_adapter;
+pub use pg_adapter::insert_entry;
@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+
+use crate::{
+    error::ApiError,
+    modules::audit::{port::DBRepository, AuditEntry, NewAuditEntry},
+    utils::database::{Filter, PaginatedRecord, Pagination, PostgresRepository, Value},
+};
+
+/// Writes the entry on the given connection, so it commits or rolls back
+/// together with the change it describes.
+pub async fn insert_entry(
+    conn: &mut PgConnection,
+    entry: &NewAuditEntry,
+) -> Result<AuditEntry, ApiError> {
+    sqlx::query_as::<_, AuditEntry>(
+        r#"
+        INSERT INTO audit_log (tenant_id, actor_user_id, entity, entity_id, action, changes)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(entry.tenant_id)
+    .bind(&entry.actor_user_id)
+    .bind(entry.entity.as_str())
+    .bind(entry.entity_id)
+    .bind(entry.action.as_str())
+    .bind(&entry.changes)
+    .fetch_one(conn)
+    .await
+    .map_err(ApiError::DatabaseError)
+}
+
+#[async_trait]
+impl DBRepository for PostgresRepository {
+    async fn create(&self, entry: NewAuditEntry) -> Result<AuditEntry, ApiError> {
+        let mut conn = self
+            .pg_pool
+            .acquire()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        insert_entry(&mut conn, &entry).await
+    }
+
+    async fn find_many(
+        &self,
+        filter: Filter,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<AuditEntry>, ApiError> {
+        let (where_clause, args) = filter.build_for_sqlx();
+
+        let offset = (pagination.page - 1) * pagination.per_page;
+
+        let count_query = format!("SELECT COUNT(*) FROM audit_log WHERE {}", where_clause);
+        let mut count_query_builder = sqlx::query_scalar::<_, i64>(&count_query);
+        for arg in args.clone() {
+            count_query_builder = match arg {
+                Value::Int(i) => count_query_builder.bind(i),
+                Value::Float(f) => count_query_builder.bind(f),
+                Value::String(s) => count_query_builder.bind(s),
+                Value::Bool(b) => count_query_builder.bind(b),
+                Value::Json(j) => count_query_builder.bind(j),
+            };
+        }
+        let total_items = count_query_builder
+            .fetch_one(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let query = format!(
+            "SELECT * FROM audit_log WHERE {} ORDER BY created_at DESC, id DESC LIMIT {} OFFSET {}",
+            where_clause, pagination.per_page, offset
+        );
+        let mut query_builder = sqlx::query_as::<_, AuditEntry>(&query);
+        for arg in args {
+            query_builder = match arg {
+                Value::Int(i) => query_builder.bind(i),
+                Value::Float(f) => query_builder.bind(f),
+                Value::String(s) => query_builder.bind(s),
+                Value::Bool(b) => query_builder.bind(b),
+                Value::Json(j) => query_builder.bind(j),
+            };
+        }
+
+        let entries = query_builder
+            .fetch_all(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        Ok(PaginatedRecord::new(
+            entries,
+            total_items as u64,
+            pagination.page,
+            pagination.per_page,
+        ))
+    }
+}
@@ -0,0 +1,12 @@
+pub mod port;
+
+mod model;
+pub use model::*;
+
+pub mod infrastructure;
+
+mod service;
+pub use service::*;
+
+mod api;
+pub use api::*;
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+use sqlx::FromRow;
+
+/// Kind of record an audit entry refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    Property,
+    Feedback,
+    Hero,
+    Config,
+    SocialMedia,
+    Tenant,
+    FeedMappings,
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Property => "property",
+            AuditEntity::Feedback => "feedback",
+            AuditEntity::Hero => "hero",
+            AuditEntity::Config => "config",
+            AuditEntity::SocialMedia => "social_media",
+            AuditEntity::Tenant => "tenant",
+            AuditEntity::FeedMappings => "feed_mappings",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub tenant_id: i32,
+    pub actor_user_id: String,
+    pub entity: String,
+    pub entity_id: i32,
+    pub action: String,
+    pub changes: JsonValue,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub tenant_id: i32,
+    pub actor_user_id: String,
+    pub entity: AuditEntity,
+    pub entity_id: i32,
+    pub action: AuditAction,
+    pub changes: JsonValue,
+}
+
+impl NewAuditEntry {
+    /// Entry for a change from `before` to `after`, either missing when the
+    /// record was created or deleted.
+    pub fn new<T: Serialize>(
+        actor_user_id: &str,
+        tenant_id: i32,
+        entity: AuditEntity,
+        entity_id: i32,
+        action: AuditAction,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Self {
+        Self {
+            tenant_id,
+            actor_user_id: actor_user_id.to_string(),
+            entity,
+            entity_id,
+            action,
+            changes: diff(before, after),
+        }
+    }
+}
+
+/// Fields that change on every write and would only add noise to a diff.
+const IGNORED_FIELDS: [&str; 2] = ["updated_at", "deleted_at"];
+
+/// Field-level changes between two snapshots as `{ field: { before, after } }`.
+/// A missing snapshot (creation or deletion) reports every field against `null`.
+pub fn diff<T: Serialize>(before: Option<&T>, after: Option<&T>) -> JsonValue {
+    let before = snapshot(before);
+    let after = snapshot(after);
+
+    let mut changes = Map::new();
+    for field in before.keys().chain(after.keys()) {
+        if changes.contains_key(field) || IGNORED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+
+        let old = before.get(field).unwrap_or(&JsonValue::Null);
+        let new = after.get(field).unwrap_or(&JsonValue::Null);
+        if old != new {
+            changes.insert(field.clone(), json!({ "before": old, "after": new }));
+        }
+    }
+
+    JsonValue::Object(changes)
+}
+
+fn snapshot<T: Serialize>(value: Option<&T>) -> Map<String, JsonValue> {
+    match value.map(serde_json::to_value) {
+        Some(Ok(JsonValue::Object(fields))) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// Optional criteria to narrow down a tenant's audit log.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    pub entity: Option<AuditEntity>,
+    pub entity_id: Option<i32>,
+    pub action: Option<AuditAction>,
+    pub actor_user_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Listing {
+        title: &'static str,
+        price: f64,
+        city: Option<&'static str>,
+        updated_at: Option<&'static str>,
+        deleted_at: Option<&'static str>,
+    }
+
+    fn listing() -> Listing {
+        Listing {
+            title: "Casa en Miraflores",
+            price: 250000.0,
+            city: Some("Lima"),
+            updated_at: Some("2024-05-01T10:00:00Z"),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn update_reports_only_changed_fields() {
+        let before = listing();
+        let after = Listing {
+            price: 240000.0,
+            city: None,
+            ..listing()
+        };
+
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            json!({
+                "price": { "before": 250000.0, "after": 240000.0 },
+                "city": { "before": "Lima", "after": null },
+            })
+        );
+    }
+
+    #[test]
+    fn timestamps_are_ignored() {
+        let before = listing();
+        let after = Listing {
+            updated_at: Some("2024-05-02T10:00:00Z"),
+            deleted_at: Some("2024-05-02T10:00:00Z"),
+            ..listing()
+        };
+
+        assert_eq!(diff(Some(&before), Some(&after)), json!({}));
+    }
+
+    #[test]
+    fn creation_reports_every_field_against_null() {
+        assert_eq!(
+            diff(None, Some(&listing())),
+            json!({
+                "title": { "before": null, "after": "Casa en Miraflores" },
+                "price": { "before": null, "after": 250000.0 },
+                "city": { "before": null, "after": "Lima" },
+            })
+        );
+    }
+
+    #[test]
+    fn deletion_reports_every_field_against_null() {
+        assert_eq!(
+            diff(Some(&listing()), None),
+            json!({
+                "title": { "before": "Casa en Miraflores", "after": null },
+                "price": { "before": 250000.0, "after": null },
+                "city": { "before": "Lima", "after": null },
+            })
+        );
+    }
+
+    #[test]
+    fn fields_missing_on_one_side_are_compared_with_null() {
+        let before = json!({ "title": "Casa", "notes": "old" });
+        let after = json!({ "title": "Casa", "bedrooms": 3 });
+
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            json!({
+                "notes": { "before": "old", "after": null },
+                "bedrooms": { "before": null, "after": 3 },
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_to_compare_is_empty() {
+        assert_eq!(diff::<Listing>(None, None), json!({}));
+    }
+}
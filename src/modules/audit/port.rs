@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::ApiError,
+    utils::database::{Filter, PaginatedRecord, Pagination},
+};
+
+use super::{AuditEntry, NewAuditEntry};
+
+#[async_trait]
+pub trait DBRepository: Send + Sync {
+    async fn create(&self, entry: NewAuditEntry) -> Result<AuditEntry, ApiError>;
+    async fn find_many(
+        &self,
+        filter: Filter,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<AuditEntry>, ApiError>;
+}
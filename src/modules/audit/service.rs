@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::{
+    error::ApiError,
+    utils::database::{Filter, FilterCondition, PaginatedRecord, Pagination},
+};
+
+use super::{port::DBRepository, AuditAction, AuditEntity, AuditEntry, AuditFilter, NewAuditEntry};
+
+pub struct Service {
+    db_repo: Arc<dyn DBRepository>,
+}
+impl Service {
+    pub fn new(db_repo: Arc<dyn DBRepository>) -> Self {
+        Self { db_repo }
+    }
+}
+
+impl Service {
+    pub async fn record_create<T: Serialize>(
+        &self,
+        actor_user_id: &str,
+        tenant_id: i32,
+        entity: AuditEntity,
+        entity_id: i32,
+        created: &T,
+    ) {
+        self.record(NewAuditEntry::new(
+            actor_user_id,
+            tenant_id,
+            entity,
+            entity_id,
+            AuditAction::Create,
+            None,
+            Some(created),
+        ))
+        .await
+    }
+
+    pub async fn record_update<T: Serialize>(
+        &self,
+        actor_user_id: &str,
+        tenant_id: i32,
+        entity: AuditEntity,
+        entity_id: i32,
+        before: &T,
+        after: &T,
+    ) {
+        self.record(NewAuditEntry::new(
+            actor_user_id,
+            tenant_id,
+            entity,
+            entity_id,
+            AuditAction::Update,
+            Some(before),
+            Some(after),
+        ))
+        .await
+    }
+
+    pub async fn record_delete<T: Serialize>(
+        &self,
+        actor_user_id: &str,
+        tenant_id: i32,
+        entity: AuditEntity,
+        entity_id: i32,
+        deleted: &T,
+    ) {
+        self.record(NewAuditEntry::new(
+            actor_user_id,
+            tenant_id,
+            entity,
+            entity_id,
+            AuditAction::Delete,
+            Some(deleted),
+            None,
+        ))
+        .await
+    }
+
+    pub async fn record_restore<T: Serialize>(
+        &self,
+        actor_user_id: &str,
+        tenant_id: i32,
+        entity: AuditEntity,
+        entity_id: i32,
+        restored: &T,
+    ) {
+        self.record(NewAuditEntry::new(
+            actor_user_id,
+            tenant_id,
+            entity,
+            entity_id,
+            AuditAction::Restore,
+            None,
+            Some(restored),
+        ))
+        .await
+    }
+
+    pub async fn find_tenant_audit_log(
+        &self,
+        tenant_id: i32,
+        audit_filter: AuditFilter,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<AuditEntry>, ApiError> {
+        let mut filter = Filter::new();
+        filter.add("tenant_id", FilterCondition::eq(tenant_id));
+        if let Some(entity) = audit_filter.entity {
+            filter.add("entity", FilterCondition::eq(entity.as_str()));
+        }
+        if let Some(entity_id) = audit_filter.entity_id {
+            filter.add("entity_id", FilterCondition::eq(entity_id));
+        }
+        if let Some(action) = audit_filter.action {
+            filter.add("action", FilterCondition::eq(action.as_str()));
+        }
+        if let Some(actor_user_id) = audit_filter.actor_user_id {
+            filter.add("actor_user_id", FilterCondition::eq(actor_user_id));
+        }
+
+        self.db_repo.find_many(filter, pagination).await
+    }
+
+    /// The audited change is already committed at this point, so a failed
+    /// write is logged instead of being reported to the caller. Repositories
+    /// that can write the entry in their own transaction use
+    /// `infrastructure::insert_entry` instead.
+    async fn record(&self, entry: NewAuditEntry) {
+        let (action, entity, entity_id) = (entry.action, entry.entity, entry.entity_id);
+
+        if let Err(e) = self.db_repo.create(entry).await {
+            log::error!(
+                "Failed to record {} of {} {}: {}",
+                action.as_str(),
+                entity.as_str(),
+                entity_id,
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    error::ApiError,
+    modules::{
+        feed::{FeedFormat, FeedMappings, Service},
+        tenant,
+    },
+    utils::{etag::conditional_response, lucia},
+};
+
+const FEED_MAX_AGE_SECS: u32 = 300;
+const MAX_MAPPED_VALUE_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateMappingsRequest {
+    #[serde(default)]
+    #[validate(custom(function = "validate_mapping"))]
+    pub property_type: HashMap<String, String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_mapping"))]
+    pub currency: HashMap<String, String>,
+}
+
+fn validate_mapping(mapping: &HashMap<String, String>) -> Result<(), ValidationError> {
+    let valid = mapping.iter().all(|(from, to)| {
+        [from, to]
+            .iter()
+            .all(|value| !value.trim().is_empty() && value.len() <= MAX_MAPPED_VALUE_LENGTH)
+    });
+
+    if valid {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("mapping");
+        error.message = Some(Cow::Owned(format!(
+            "values must be between 1 and {} characters",
+            MAX_MAPPED_VALUE_LENGTH
+        )));
+        Err(error)
+    }
+}
+
+pub async fn get_portal_feed(
+    service: web::Data<Arc<Service>>,
+    tenant_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    feed_response(&service, *tenant_id, FeedFormat::PortalXml, &req_headers).await
+}
+
+pub async fn get_json_ld_feed(
+    service: web::Data<Arc<Service>>,
+    tenant_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    feed_response(&service, *tenant_id, FeedFormat::JsonLd, &req_headers).await
+}
+
+async fn feed_response(
+    service: &Service,
+    tenant_id: i32,
+    format: FeedFormat,
+    req_headers: &HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let feed = service.render_feed(tenant_id, format).await?;
+
+    Ok(conditional_response(
+        req_headers,
+        feed.as_bytes().to_vec(),
+        format.content_type(),
+        FEED_MAX_AGE_SECS,
+    ))
+}
+
+pub async fn get_mappings(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let mappings = service.find_tenant_mappings(tenant.id).await?;
+    Ok(HttpResponse::Ok().json(mappings))
+}
+
+pub async fn update_mappings(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    req_headers: HttpRequest,
+    web::Json(update_request): web::Json<UpdateMappingsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    update_request.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let mappings = FeedMappings {
+        property_type: update_request.property_type,
+        currency: update_request.currency,
+    };
+    let mappings = service
+        .update_tenant_mappings(tenant.id, mappings, &session.user_id)
+        .await?;
+    Ok(HttpResponse::Ok().json(mappings))
+}
@@ -0,0 +1,17 @@
+use actix_web::web;
+use handler::{get_json_ld_feed, get_mappings, get_portal_feed, update_mappings};
+
+mod handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/feeds")
+            .route("/mappings", web::get().to(get_mappings))
+            .route("/mappings", web::put().to(update_mappings))
+            .route("/{tenant_id}/portal.xml", web::get().to(get_portal_feed))
+            .route(
+                "/{tenant_id}/listings.jsonld",
+                web::get().to(get_json_ld_feed),
+            ),
+    );
+}
@@ -0,0 +1,47 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::FeedFormat;
+
+#[derive(Default)]
+struct Entries {
+    feeds: HashMap<(i32, FeedFormat), Arc<String>>,
+    /// Bumped on every invalidation so renders started before it are not stored.
+    generations: HashMap<i32, u64>,
+}
+
+/// Rendered feeds, kept until one of the tenant's listings or mappings changes.
+#[derive(Default)]
+pub struct FeedCache {
+    entries: Mutex<Entries>,
+}
+
+impl FeedCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached feed, or the generation to hand back to `insert` once rendered.
+    pub fn get(&self, tenant_id: i32, format: FeedFormat) -> Result<Arc<String>, u64> {
+        let entries = self.entries.lock().unwrap();
+        match entries.feeds.get(&(tenant_id, format)) {
+            Some(feed) => Ok(feed.clone()),
+            None => Err(entries.generations.get(&tenant_id).copied().unwrap_or(0)),
+        }
+    }
+
+    pub fn insert(&self, tenant_id: i32, format: FeedFormat, generation: u64, feed: Arc<String>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.generations.get(&tenant_id).copied().unwrap_or(0) == generation {
+            entries.feeds.insert((tenant_id, format), feed);
+        }
+    }
+
+    pub fn invalidate(&self, tenant_id: i32) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.feeds.retain(|(id, _), _| *id != tenant_id);
+        *entries.generations.entry(tenant_id).or_insert(0) += 1;
+    }
+}
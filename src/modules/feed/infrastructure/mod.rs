@@ -0,0 +1 @@
+mod pg_adapter;
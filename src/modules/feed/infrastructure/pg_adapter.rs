@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::types::Json;
+
+use crate::{
+    error::ApiError,
+    modules::feed::{port::DBRepository, FeedMappings},
+    utils::database::PostgresRepository,
+};
+
+type MappingColumns = (Json<HashMap<String, String>>, Json<HashMap<String, String>>);
+
+#[async_trait]
+impl DBRepository for PostgresRepository {
+    async fn find_mappings(&self, tenant_id: i32) -> Result<FeedMappings, ApiError> {
+        let mappings = sqlx::query_as::<_, MappingColumns>(
+            "SELECT property_type, currency FROM feed_mappings WHERE tenant_id = $1",
+        )
+        .bind(tenant_id)
+        .fetch_optional(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(mappings
+            .map(|(property_type, currency)| FeedMappings {
+                property_type: property_type.0,
+                currency: currency.0,
+            })
+            .unwrap_or_default())
+    }
+
+    async fn upsert_mappings(
+        &self,
+        tenant_id: i32,
+        mappings: &FeedMappings,
+    ) -> Result<FeedMappings, ApiError> {
+        let (property_type, currency) = sqlx::query_as::<_, MappingColumns>(
+            r#"
+            INSERT INTO feed_mappings (tenant_id, property_type, currency)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tenant_id) DO UPDATE
+            SET property_type = EXCLUDED.property_type,
+                currency = EXCLUDED.currency,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING property_type, currency
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(Json(&mappings.property_type))
+        .bind(Json(&mappings.currency))
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(FeedMappings {
+            property_type: property_type.0,
+            currency: currency.0,
+        })
+    }
+}
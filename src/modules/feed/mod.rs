@@ -0,0 +1,17 @@
+pub mod port;
+
+mod model;
+pub use model::*;
+
+pub mod infrastructure;
+
+mod cache;
+pub use cache::*;
+
+mod render;
+
+mod service;
+pub use service::*;
+
+mod api;
+pub use api::*;
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Syndication formats a tenant's listings are published in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedFormat {
+    /// Generic XML schema ingested by real-estate portals.
+    PortalXml,
+    /// schema.org `RealEstateListing` items as JSON-LD.
+    JsonLd,
+}
+
+impl FeedFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::PortalXml => "application/xml; charset=utf-8",
+            FeedFormat::JsonLd => "application/ld+json",
+        }
+    }
+}
+
+/// Translation of a tenant's own `property_type` and `currency` values into
+/// the ones portals expect. Values without a mapping are published as is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedMappings {
+    #[serde(default)]
+    pub property_type: HashMap<String, String>,
+    #[serde(default)]
+    pub currency: HashMap<String, String>,
+}
+
+impl FeedMappings {
+    pub fn property_type<'a>(&'a self, property_type: &'a str) -> &'a str {
+        self.property_type
+            .get(property_type)
+            .map_or(property_type, String::as_str)
+    }
+
+    pub fn currency<'a>(&'a self, currency: &'a str) -> &'a str {
+        self.currency.get(currency).map_or(currency, String::as_str)
+    }
+}
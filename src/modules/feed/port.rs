@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::error::ApiError;
+
+use super::FeedMappings;
+
+#[async_trait]
+pub trait DBRepository: Send + Sync {
+    /// Mappings of the tenant, empty when none were configured.
+    async fn find_mappings(&self, tenant_id: i32) -> Result<FeedMappings, ApiError>;
+    async fn upsert_mappings(
+        &self,
+        tenant_id: i32,
+        mappings: &FeedMappings,
+    ) -> Result<FeedMappings, ApiError>;
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write;
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::{modules::property::PropertyWithImages, utils::xml::escape};
+
+use super::FeedMappings;
+
+/// Renders the listings in the generic portal XML schema.
+pub fn portal_xml(properties: &[PropertyWithImages], mappings: &FeedMappings) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<listings generated_at=\"{}\">",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+    );
+
+    for PropertyWithImages { property, images } in properties {
+        xml.push_str("  <listing>\n");
+        element(&mut xml, 4, "id", &property.id.to_string());
+        element(&mut xml, 4, "title", &property.title);
+        optional(&mut xml, 4, "description", property.description.as_deref());
+        element(
+            &mut xml,
+            4,
+            "property_type",
+            mappings.property_type(&property.property_type),
+        );
+        element(&mut xml, 4, "status", &property.status);
+        let _ = writeln!(
+            xml,
+            "    <price currency=\"{}\">{:.2}</price>",
+            escape(mappings.currency(&property.currency)),
+            property.price
+        );
+        optional(
+            &mut xml,
+            4,
+            "bedrooms",
+            property.bedrooms.map(|v| v.to_string()).as_deref(),
+        );
+        optional(
+            &mut xml,
+            4,
+            "bathrooms",
+            property.bathrooms.map(|v| v.to_string()).as_deref(),
+        );
+        optional(
+            &mut xml,
+            4,
+            "parking_spaces",
+            property.parking_spaces.map(|v| v.to_string()).as_deref(),
+        );
+        optional(
+            &mut xml,
+            4,
+            "total_area",
+            property.total_area.map(|v| v.to_string()).as_deref(),
+        );
+        optional(
+            &mut xml,
+            4,
+            "built_area",
+            property.built_area.map(|v| v.to_string()).as_deref(),
+        );
+        optional(
+            &mut xml,
+            4,
+            "year_built",
+            property.year_built.map(|v| v.to_string()).as_deref(),
+        );
+
+        xml.push_str("    <location>\n");
+        optional(&mut xml, 6, "address", property.address.as_deref());
+        optional(&mut xml, 6, "city", property.city.as_deref());
+        optional(&mut xml, 6, "state", property.state.as_deref());
+        optional(&mut xml, 6, "country", property.country.as_deref());
+        optional(&mut xml, 6, "map_url", property.google_maps_url.as_deref());
+        xml.push_str("    </location>\n");
+
+        if let Some(amenities) = property.amenities.as_ref().filter(|a| !a.is_empty()) {
+            xml.push_str("    <features>\n");
+            for amenity in amenities {
+                element(&mut xml, 6, "feature", amenity);
+            }
+            xml.push_str("    </features>\n");
+        }
+
+        if !images.is_empty() {
+            xml.push_str("    <pictures>\n");
+            for image in images {
+                let _ = writeln!(
+                    xml,
+                    "      <picture primary=\"{}\">{}</picture>",
+                    image.is_primary,
+                    escape(&image.image_url)
+                );
+            }
+            xml.push_str("    </pictures>\n");
+        }
+
+        optional(
+            &mut xml,
+            4,
+            "updated_at",
+            property
+                .updated_at
+                .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .as_deref(),
+        );
+        xml.push_str("  </listing>\n");
+    }
+
+    xml.push_str("</listings>\n");
+    xml
+}
+
+/// Renders the listings as a schema.org `RealEstateListing` graph.
+pub fn json_ld(properties: &[PropertyWithImages], mappings: &FeedMappings) -> JsonValue {
+    let listings: Vec<JsonValue> = properties
+        .iter()
+        .map(|PropertyWithImages { property, images }| {
+            let mut address = Map::new();
+            address.insert("@type".into(), json!("PostalAddress"));
+            insert_some(&mut address, "streetAddress", &property.address);
+            insert_some(&mut address, "addressLocality", &property.city);
+            insert_some(&mut address, "addressRegion", &property.state);
+            insert_some(&mut address, "addressCountry", &property.country);
+
+            let mut accommodation = Map::new();
+            accommodation.insert("@type".into(), json!("Accommodation"));
+            accommodation.insert(
+                "accommodationCategory".into(),
+                json!(mappings.property_type(&property.property_type)),
+            );
+            insert_some(&mut accommodation, "numberOfBedrooms", &property.bedrooms);
+            insert_some(
+                &mut accommodation,
+                "numberOfBathroomsTotal",
+                &property.bathrooms,
+            );
+            insert_some(&mut accommodation, "yearBuilt", &property.year_built);
+            if let Some(area) = property.built_area.or(property.total_area) {
+                accommodation.insert(
+                    "floorSize".into(),
+                    json!({ "@type": "QuantitativeValue", "value": area, "unitCode": "MTK" }),
+                );
+            }
+            if let Some(amenities) = &property.amenities {
+                accommodation.insert(
+                    "amenityFeature".into(),
+                    amenities
+                        .iter()
+                        .map(|amenity| {
+                            json!({
+                                "@type": "LocationFeatureSpecification",
+                                "name": amenity,
+                                "value": true,
+                            })
+                        })
+                        .collect(),
+                );
+            }
+            accommodation.insert("address".into(), JsonValue::Object(address));
+
+            let mut listing = Map::new();
+            listing.insert("@type".into(), json!("RealEstateListing"));
+            listing.insert("identifier".into(), json!(property.id.to_string()));
+            listing.insert("name".into(), json!(property.title));
+            insert_some(&mut listing, "description", &property.description);
+            insert_some(&mut listing, "dateModified", &property.updated_at);
+            insert_some(&mut listing, "hasMap", &property.google_maps_url);
+            listing.insert(
+                "image".into(),
+                images.iter().map(|image| json!(image.image_url)).collect(),
+            );
+            listing.insert(
+                "offers".into(),
+                json!({
+                    "@type": "Offer",
+                    "price": property.price,
+                    "priceCurrency": mappings.currency(&property.currency),
+                    "itemOffered": accommodation,
+                }),
+            );
+            JsonValue::Object(listing)
+        })
+        .collect();
+
+    json!({
+        "@context": "https://schema.org",
+        "@graph": listings,
+    })
+}
+
+fn element(xml: &mut String, indent: usize, name: &str, value: &str) {
+    let _ = writeln!(xml, "{:indent$}<{name}>{}</{name}>", "", escape(value));
+}
+
+fn optional(xml: &mut String, indent: usize, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        element(xml, indent, name, value);
+    }
+}
+
+fn insert_some<T: serde::Serialize>(
+    map: &mut Map<String, JsonValue>,
+    key: &str,
+    value: &Option<T>,
+) {
+    if let Some(value) = value {
+        map.insert(key.into(), json!(value));
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+
+use crate::{
+    error::ApiError,
+    modules::{
+        audit::{self, AuditEntity},
+        property::{self, PropertyWithImages},
+    },
+};
+
+use super::{port::DBRepository, render, FeedCache, FeedFormat, FeedMappings};
+
+pub struct Service {
+    db_repo: Arc<dyn DBRepository>,
+    property_service: Arc<property::Service>,
+    audit_service: Arc<audit::Service>,
+    cache: Arc<FeedCache>,
+}
+impl Service {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        property_service: Arc<property::Service>,
+        audit_service: Arc<audit::Service>,
+        cache: Arc<FeedCache>,
+    ) -> Self {
+        Self {
+            db_repo,
+            property_service,
+            audit_service,
+            cache,
+        }
+    }
+}
+
+impl Service {
+    /// Renders the tenant's published listings, reusing the cached feed until
+    /// a listing or mapping changes.
+    pub async fn render_feed(
+        &self,
+        tenant_id: i32,
+        format: FeedFormat,
+    ) -> Result<Arc<String>, ApiError> {
+        let generation = match self.cache.get(tenant_id, format) {
+            Ok(feed) => return Ok(feed),
+            Err(generation) => generation,
+        };
+
+        let mappings = self.db_repo.find_mappings(tenant_id).await?;
+        let properties: Vec<PropertyWithImages> = self
+            .property_service
+            .export_tenant_properties(tenant_id)
+            .try_collect()
+            .await?;
+
+        let feed = Arc::new(match format {
+            FeedFormat::PortalXml => render::portal_xml(&properties, &mappings),
+            FeedFormat::JsonLd => render::json_ld(&properties, &mappings).to_string(),
+        });
+        self.cache
+            .insert(tenant_id, format, generation, feed.clone());
+
+        Ok(feed)
+    }
+
+    pub async fn find_tenant_mappings(&self, tenant_id: i32) -> Result<FeedMappings, ApiError> {
+        self.db_repo.find_mappings(tenant_id).await
+    }
+
+    pub async fn update_tenant_mappings(
+        &self,
+        tenant_id: i32,
+        mappings: FeedMappings,
+        actor_user_id: &str,
+    ) -> Result<FeedMappings, ApiError> {
+        let before = self.db_repo.find_mappings(tenant_id).await?;
+        let updated = self.db_repo.upsert_mappings(tenant_id, &mappings).await?;
+        self.cache.invalidate(tenant_id);
+
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                tenant_id,
+                AuditEntity::FeedMappings,
+                tenant_id,
+                &before,
+                &updated,
+            )
+            .await;
+
+        Ok(updated)
+    }
+}
@@ -1,22 +1,41 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{http::header::ETag, web, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
     error::ApiError,
     modules::{
-        front::landing::config::{Config, Service},
+        front::landing::config::{Config, ConfigPatch, Service},
         tenant,
     },
-    utils::lucia,
+    utils::{
+        etag::{entity_tag, Precondition},
+        lucia,
+        patch::non_null,
+        validation::validate_hex_color,
+    },
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct UpdateConfig {
+    #[validate(length(min = 1, max = 255))]
     pub logo: String,
+    #[validate(custom(function = "validate_hex_color"))]
     pub color: String,
 }
 
+/// JSON merge patch body: absent members are left unchanged.
+#[derive(Deserialize, Validate)]
+pub struct PatchConfig {
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 255))]
+    pub logo: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(custom(function = "validate_hex_color"))]
+    pub color: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct PresignedUrlResponse {
     pub url: String,
@@ -29,6 +48,8 @@ pub async fn update_config(
     req: web::Json<UpdateConfig>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -47,9 +68,51 @@ pub async fn update_config(
 
     let config = Config::new(tenant.id, &req.logo, &req.color);
 
-    let updated_config = service.update_config(config).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let updated_config = service
+        .update_config(config, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(updated_config.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(updated_config))
+}
+
+pub async fn patch_config(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Json(req): web::Json<PatchConfig>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
 
-    Ok(HttpResponse::Ok().json(updated_config))
+    let patch = ConfigPatch {
+        logo: req.logo,
+        color: req.color,
+    };
+
+    let precondition = Precondition::from_request(&req_headers)?;
+    let patched_config = service
+        .patch_config(tenant.id, patch, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(patched_config.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(patched_config))
 }
 
 pub async fn get_tenant_config(
@@ -57,7 +120,12 @@ pub async fn get_tenant_config(
     tenant_id: web::Path<i32>,
 ) -> Result<HttpResponse, ApiError> {
     let config = service.find_tenant_config(*tenant_id).await?;
-    Ok(HttpResponse::Ok().json(config))
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(config.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(config))
 }
 
 pub async fn generate_logo_presigned_url(
@@ -1,5 +1,5 @@
 use actix_web::web;
-use handler::{generate_logo_presigned_url, get_tenant_config, update_config};
+use handler::{generate_logo_presigned_url, get_tenant_config, patch_config, update_config};
 
 mod handler;
 
@@ -7,6 +7,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/config")
             .route("", web::put().to(update_config))
+            .route("", web::patch().to(patch_config))
             .route("/tenant/{tenant_id}", web::get().to(get_tenant_config))
             .route(
                 "/logo-upload-url",
@@ -1,14 +1,41 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::Row;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 
 use crate::error::ApiError;
 use crate::modules::front::landing::config::port::DBRepository;
-use crate::modules::front::landing::config::Config;
-use crate::utils::database::{Filter, PostgresRepository, Value};
+use crate::modules::front::landing::config::{Config, ConfigPatch};
+use crate::utils::database::{push_set, Filter, PostgresRepository, Value};
+use crate::utils::etag::Precondition;
+
+/// Locks the tenant's config row for the rest of the transaction and returns its version.
+async fn lock_config(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+) -> Result<Option<DateTime<Utc>>, ApiError> {
+    sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT updated_at FROM config WHERE tenant_id = $1 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(ApiError::DatabaseError)?
+    .ok_or_else(|| ApiError::NotFound(format!("Config for tenant_id {} not found", tenant_id)))
+}
 
 #[async_trait]
 impl DBRepository for PostgresRepository {
-    async fn edit(&self, config: Config) -> Result<Config, ApiError> {
+    async fn edit(&self, config: Config, precondition: &Precondition) -> Result<Config, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_config(&mut tx, config.tenant_id).await?;
+        precondition.ensure(current_version)?;
+
         let updated_config = sqlx::query(
             r#"
             UPDATE config
@@ -20,7 +47,7 @@ impl DBRepository for PostgresRepository {
         .bind(&config.logo)
         .bind(&config.color)
         .bind(&config.tenant_id)
-        .fetch_one(&*self.pg_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => ApiError::NotFound(format!(
@@ -30,11 +57,62 @@ impl DBRepository for PostgresRepository {
             _ => ApiError::DatabaseError(e),
         })?;
 
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
         Ok(Config {
             id: updated_config.get("id"),
             tenant_id: updated_config.get("tenant_id"),
             logo: updated_config.get("logo"),
             color: updated_config.get("color"),
+            updated_at: updated_config.get("updated_at"),
+        })
+    }
+
+    async fn patch(
+        &self,
+        tenant_id: i32,
+        patch: ConfigPatch,
+        precondition: &Precondition,
+    ) -> Result<Config, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_config(&mut tx, tenant_id).await?;
+        precondition.ensure(current_version)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE config SET ");
+        let mut set = query.separated(", ");
+        push_set(&mut set, "logo", patch.logo);
+        push_set(&mut set, "color", patch.color);
+        set.push("updated_at = CURRENT_TIMESTAMP");
+
+        query
+            .push(" WHERE tenant_id = ")
+            .push_bind(tenant_id)
+            .push(" RETURNING *");
+
+        let patched_config = query
+            .build()
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => {
+                    ApiError::NotFound(format!("Config for tenant_id {} not found", tenant_id))
+                }
+                _ => ApiError::DatabaseError(e),
+            })?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(Config {
+            id: patched_config.get("id"),
+            tenant_id: patched_config.get("tenant_id"),
+            logo: patched_config.get("logo"),
+            color: patched_config.get("color"),
+            updated_at: patched_config.get("updated_at"),
         })
     }
 
@@ -68,6 +146,7 @@ impl DBRepository for PostgresRepository {
             tenant_id: config.get("tenant_id"),
             logo: config.get("logo"),
             color: config.get("color"),
+            updated_at: config.get("updated_at"),
         })
     }
 }
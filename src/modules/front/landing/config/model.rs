@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -7,6 +8,7 @@ pub struct Config {
     pub tenant_id: i32,
     pub logo: String,
     pub color: String,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 impl Config {
     pub fn new(tenant_id: i32, logo: &str, color: &str) -> Self {
@@ -15,6 +17,14 @@ impl Config {
             tenant_id,
             logo: logo.into(),
             color: color.into(),
+            updated_at: None,
         }
     }
 }
+
+/// Columns to change on a config; `None` leaves a column untouched.
+#[derive(Debug, Default)]
+pub struct ConfigPatch {
+    pub logo: Option<String>,
+    pub color: Option<String>,
+}
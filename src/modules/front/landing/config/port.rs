@@ -1,12 +1,21 @@
 use async_trait::async_trait;
 
-use crate::{error::ApiError, utils::database::Filter};
+use crate::{
+    error::ApiError,
+    utils::{database::Filter, etag::Precondition},
+};
 
-use super::Config;
+use super::{Config, ConfigPatch};
 
 #[async_trait]
 pub trait DBRepository: Send + Sync {
-    async fn edit(&self, config: Config) -> Result<Config, ApiError>;
+    async fn edit(&self, config: Config, precondition: &Precondition) -> Result<Config, ApiError>;
+    async fn patch(
+        &self,
+        tenant_id: i32,
+        patch: ConfigPatch,
+        precondition: &Precondition,
+    ) -> Result<Config, ApiError>;
     async fn find(&self, fileter: Filter) -> Result<Config, ApiError>;
 }
 
@@ -4,23 +4,33 @@ use uuid::Uuid;
 
 use crate::{
     error::ApiError,
-    utils::database::{Filter, FilterCondition},
+    modules::audit::{self, AuditEntity},
+    utils::{
+        database::{Filter, FilterCondition},
+        etag::Precondition,
+    },
 };
 
 use super::{
     port::{BucketRepository, DBRepository},
-    Config,
+    Config, ConfigPatch,
 };
 
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
     bucket_repo: Arc<dyn BucketRepository>,
+    audit_service: Arc<audit::Service>,
 }
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>, bucket_repo: Arc<dyn BucketRepository>) -> Self {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        bucket_repo: Arc<dyn BucketRepository>,
+        audit_service: Arc<audit::Service>,
+    ) -> Self {
         Self {
             db_repo,
             bucket_repo,
+            audit_service,
         }
     }
 }
@@ -31,8 +41,49 @@ impl Service {
         self.bucket_repo.post_presigned_url(&key).await
     }
 
-    pub async fn update_config(&self, config: Config) -> Result<Config, ApiError> {
-        self.db_repo.edit(config).await
+    pub async fn update_config(
+        &self,
+        config: Config,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Config, ApiError> {
+        let before = self.find_tenant_config(config.tenant_id).await?;
+        let updated = self.db_repo.edit(config, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                updated.tenant_id,
+                AuditEntity::Config,
+                updated.id,
+                &before,
+                &updated,
+            )
+            .await;
+
+        Ok(updated)
+    }
+
+    pub async fn patch_config(
+        &self,
+        tenant_id: i32,
+        patch: ConfigPatch,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Config, ApiError> {
+        let before = self.find_tenant_config(tenant_id).await?;
+        let patched = self.db_repo.patch(tenant_id, patch, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                tenant_id,
+                AuditEntity::Config,
+                patched.id,
+                &before,
+                &patched,
+            )
+            .await;
+
+        Ok(patched)
     }
 
     pub async fn find_tenant_config(&self, tenant_id: i32) -> Result<Config, ApiError> {
@@ -1,6 +1,7 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{http::header::ETag, web, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
     error::ApiError,
@@ -8,18 +9,51 @@ use crate::{
         front::landing::feedback::{Feedback, Service},
         tenant,
     },
-    utils::{database::Pagination, lucia},
+    utils::{
+        database::Pagination,
+        etag::{entity_tag, Precondition},
+        export::{export_response, CsvRecord, ExportQuery},
+        lucia,
+    },
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct CreateUpdateFeedback {
+    #[validate(length(min = 1, max = 255))]
     pub property_image: String,
+    #[validate(length(min = 1, max = 255))]
     pub customer_image: String,
+    #[validate(length(min = 1, max = 100))]
     pub customer_name: String,
+    #[validate(length(min = 1))]
     pub customer_review: String,
     pub description: String,
 }
 
+impl CsvRecord for Feedback {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "id",
+            "customer_name",
+            "customer_review",
+            "description",
+            "customer_image",
+            "property_image",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.customer_name.clone(),
+            self.customer_review.clone(),
+            self.description.clone(),
+            self.customer_image.clone(),
+            self.property_image.clone(),
+        ]
+    }
+}
+
 #[derive(Serialize)]
 pub struct PresignedUrlResponse {
     pub url: String,
@@ -32,6 +66,8 @@ pub async fn create_feedback(
     req: web::Json<CreateUpdateFeedback>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -50,7 +86,7 @@ pub async fn create_feedback(
         &req.description,
     );
 
-    let created_feedback = service.create_feedback(feedback).await?;
+    let created_feedback = service.create_feedback(feedback, &session.user_id).await?;
     Ok(HttpResponse::Ok().json(created_feedback))
 }
 
@@ -58,9 +94,12 @@ pub async fn update_feedback(
     service: web::Data<Arc<Service>>,
     tenant_service: web::Data<Arc<tenant::Service>>,
     lucia_service: web::Data<Arc<lucia::Service>>,
+    feedback_id: web::Path<i32>,
     req: web::Json<CreateUpdateFeedback>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -70,7 +109,7 @@ pub async fn update_feedback(
     let session = lucia_service.get_session(basic_auth_header).await?;
     let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
 
-    let feedback = Feedback::new(
+    let mut feedback = Feedback::new(
         tenant.id,
         &req.property_image,
         &req.customer_image,
@@ -78,9 +117,31 @@ pub async fn update_feedback(
         &req.customer_review,
         &req.description,
     );
+    feedback.id = *feedback_id;
+
+    let precondition = Precondition::from_request(&req_headers)?;
+    let updated_feedback = service
+        .update_feedback(feedback, &precondition, &session.user_id)
+        .await?;
 
-    let updated_feedback = service.update_feedback(feedback).await?;
-    Ok(HttpResponse::Ok().json(updated_feedback))
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(updated_feedback.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(updated_feedback))
+}
+
+pub async fn get_feedback_by_id(
+    service: web::Data<Arc<Service>>,
+    feedback_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let feedback = service.find_feedback_by_id(*feedback_id).await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(feedback.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(feedback))
 }
 
 pub async fn get_tenant_feedbacks(
@@ -130,6 +191,78 @@ pub async fn delete_feedback(
     let session = lucia_service.get_session(basic_auth_header).await?;
     let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
 
-    let deleted_feedback = service.delete_feedback(*feedback_id, tenant.id).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let deleted_feedback = service
+        .delete_feedback(*feedback_id, tenant.id, &precondition, &session.user_id)
+        .await?;
     Ok(HttpResponse::Ok().json(deleted_feedback))
 }
+
+pub async fn get_trash(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    query: web::Query<Pagination>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let trash = service
+        .find_tenant_trash(tenant.id, query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(trash))
+}
+
+pub async fn restore_feedback(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    feedback_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let restored_feedback = service
+        .restore_feedback(*feedback_id, tenant.id, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(restored_feedback.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(restored_feedback))
+}
+
+pub async fn export_feedback(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    query: web::Query<ExportQuery>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let feedback = service.export_tenant_feedback(tenant.id);
+    Ok(export_response(feedback, query.format, "feedback"))
+}
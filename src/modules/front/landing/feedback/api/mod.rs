@@ -1,7 +1,7 @@
 use actix_web::web;
 use handler::{
-    create_feedback, delete_feedback, generate_image_presigned_url, get_tenant_feedbacks,
-    update_feedback,
+    create_feedback, delete_feedback, export_feedback, generate_image_presigned_url,
+    get_feedback_by_id, get_tenant_feedbacks, get_trash, restore_feedback, update_feedback,
 };
 
 mod handler;
@@ -10,12 +10,16 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/feedback")
             .route("", web::post().to(create_feedback))
-            .route("", web::put().to(update_feedback))
             .route("/tenant/{tenant_id}", web::get().to(get_tenant_feedbacks))
             .route(
                 "/image-upload-url",
                 web::get().to(generate_image_presigned_url),
             )
+            .route("/trash", web::get().to(get_trash))
+            .route("/export", web::get().to(export_feedback))
+            .route("/{feedback_id}/restore", web::post().to(restore_feedback))
+            .route("/{feedback_id}", web::get().to(get_feedback_by_id))
+            .route("/{feedback_id}", web::put().to(update_feedback))
             .route("/{feedback_id}", web::delete().to(delete_feedback)),
     );
 }
@@ -1,14 +1,69 @@
 use async_trait::async_trait;
-use sqlx::Row;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use sqlx::{PgConnection, Row};
 
 use crate::error::ApiError;
 use crate::modules::front::landing::feedback::port::DBRepository;
 use crate::modules::front::landing::feedback::Feedback;
-use crate::utils::database::{Filter, PaginatedRecord, Pagination, PostgresRepository, Value};
+use crate::modules::plan;
+use crate::utils::database::{
+    cursor_stream, Filter, PaginatedRecord, Pagination, PostgresRepository, Value,
+};
+use crate::utils::etag::Precondition;
+
+/// Locks the feedback row for the rest of the transaction and returns its version.
+async fn lock_feedback(
+    conn: &mut PgConnection,
+    id: i32,
+    tenant_id: i32,
+) -> Result<Option<DateTime<Utc>>, ApiError> {
+    sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT updated_at FROM feedback WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(ApiError::DatabaseError)?
+    .ok_or_else(|| ApiError::NotFound(format!("Feedback with id {} not found", id)))
+}
+
+/// Fails when one more active feedback entry would take the tenant past `limit`.
+/// The tenant stays locked until the transaction ends, so concurrent creates
+/// are counted one after the other.
+async fn reserve_feedback(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+    limit: Option<u32>,
+) -> Result<(), ApiError> {
+    if limit.is_none() {
+        return Ok(());
+    }
+
+    let usage = plan::infrastructure::lock_usage(conn, tenant_id).await?;
+    plan::check_limit(
+        "max_feedback_entries",
+        limit,
+        usage.feedback_entries as usize + 1,
+    )
+}
 
 #[async_trait]
 impl DBRepository for PostgresRepository {
-    async fn create(&self, feedback: Feedback) -> Result<Feedback, ApiError> {
+    async fn create(
+        &self,
+        feedback: Feedback,
+        max_feedback_entries: Option<u32>,
+    ) -> Result<Feedback, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        reserve_feedback(&mut tx, feedback.tenant_id, max_feedback_entries).await?;
+
         let created_feedback = sqlx::query(
             r#"
             INSERT INTO feedback (tenant_id, property_image, customer_image, customer_name, customer_review, description)
@@ -22,10 +77,12 @@ impl DBRepository for PostgresRepository {
         .bind(&feedback.customer_name)
         .bind(&feedback.customer_review)
         .bind(&feedback.description)
-        .fetch_one(&*self.pg_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(ApiError::DatabaseError)?;
 
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
         Ok(Feedback {
             id: created_feedback.get("id"),
             tenant_id: created_feedback.get("tenant_id"),
@@ -34,10 +91,25 @@ impl DBRepository for PostgresRepository {
             customer_name: created_feedback.get("customer_name"),
             customer_review: created_feedback.get("customer_review"),
             description: created_feedback.get("description"),
+            updated_at: created_feedback.get("updated_at"),
+            deleted_at: created_feedback.get("deleted_at"),
         })
     }
 
-    async fn edit(&self, feedback: Feedback) -> Result<Feedback, ApiError> {
+    async fn edit(
+        &self,
+        feedback: Feedback,
+        precondition: &Precondition,
+    ) -> Result<Feedback, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_feedback(&mut tx, feedback.id, feedback.tenant_id).await?;
+        precondition.ensure(current_version)?;
+
         let updated_feedback = sqlx::query(
             r#"
             UPDATE feedback
@@ -53,13 +125,15 @@ impl DBRepository for PostgresRepository {
         .bind(&feedback.description)
         .bind(&feedback.id)
         .bind(&feedback.tenant_id)
-        .fetch_one(&*self.pg_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|err| match err {
             sqlx::Error::RowNotFound => ApiError::NotFound(format!("Feedback with id {} not found", feedback.id)),
             _ => ApiError::DatabaseError(err.into()),
         })?;
 
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
         Ok(Feedback {
             id: updated_feedback.get("id"),
             tenant_id: updated_feedback.get("tenant_id"),
@@ -68,6 +142,46 @@ impl DBRepository for PostgresRepository {
             customer_name: updated_feedback.get("customer_name"),
             customer_review: updated_feedback.get("customer_review"),
             description: updated_feedback.get("description"),
+            updated_at: updated_feedback.get("updated_at"),
+            deleted_at: updated_feedback.get("deleted_at"),
+        })
+    }
+
+    async fn find(&self, filter: Filter) -> Result<Feedback, ApiError> {
+        let (where_clause, args) = filter.build_for_sqlx();
+
+        let query = format!("SELECT * FROM feedback WHERE {} LIMIT 1", where_clause);
+
+        let mut query_builder = sqlx::query(&query);
+
+        for arg in args {
+            query_builder = match arg {
+                Value::Int(i) => query_builder.bind(i),
+                Value::Float(f) => query_builder.bind(f),
+                Value::String(s) => query_builder.bind(s),
+                Value::Bool(b) => query_builder.bind(b),
+                Value::Json(j) => query_builder.bind(j),
+            };
+        }
+
+        let feedback = query_builder
+            .fetch_one(&*self.pg_pool)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => ApiError::NotFound("Feedback not found".to_string()),
+                _ => ApiError::DatabaseError(e),
+            })?;
+
+        Ok(Feedback {
+            id: feedback.get("id"),
+            tenant_id: feedback.get("tenant_id"),
+            property_image: feedback.get("property_image"),
+            customer_image: feedback.get("customer_image"),
+            customer_name: feedback.get("customer_name"),
+            customer_review: feedback.get("customer_review"),
+            description: feedback.get("description"),
+            updated_at: feedback.get("updated_at"),
+            deleted_at: feedback.get("deleted_at"),
         })
     }
 
@@ -130,6 +244,8 @@ impl DBRepository for PostgresRepository {
                 customer_name: row.get("customer_name"),
                 customer_review: row.get("customer_review"),
                 description: row.get("description"),
+                updated_at: row.get("updated_at"),
+                deleted_at: row.get("deleted_at"),
             })
             .collect();
 
@@ -141,18 +257,47 @@ impl DBRepository for PostgresRepository {
         ))
     }
 
-    async fn delete(&self, id: i32, tenant_id: i32) -> Result<Feedback, ApiError> {
+    fn stream(&self, filter: Filter) -> BoxStream<'static, Result<Feedback, ApiError>> {
+        let (where_clause, args) = filter.build_for_sqlx();
+        let query = format!("SELECT * FROM feedback WHERE {} ORDER BY id", where_clause);
+
+        cursor_stream(self.pg_pool.clone(), query, args, |row| {
+            Ok(Feedback {
+                id: row.get("id"),
+                tenant_id: row.get("tenant_id"),
+                property_image: row.get("property_image"),
+                customer_image: row.get("customer_image"),
+                customer_name: row.get("customer_name"),
+                customer_review: row.get("customer_review"),
+                description: row.get("description"),
+                updated_at: row.get("updated_at"),
+                deleted_at: row.get("deleted_at"),
+            })
+        })
+    }
+
+    async fn delete(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+    ) -> Result<Feedback, ApiError> {
         let mut tx = self
             .pg_pool
             .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-        // Fetch the feedback to return it after deletion
+        let current_version = lock_feedback(&mut tx, id, tenant_id).await?;
+        precondition.ensure(current_version)?;
+
+        // Move the feedback to the trash; the purge job removes it for good
         let feedback_row = sqlx::query(
             r#"
-            SELECT * FROM feedback
+            UPDATE feedback
+            SET deleted_at = CURRENT_TIMESTAMP
             WHERE id = $1 AND tenant_id = $2
+            RETURNING *
             "#,
         )
         .bind(id)
@@ -163,10 +308,12 @@ impl DBRepository for PostgresRepository {
             sqlx::Error::RowNotFound => {
                 ApiError::NotFound(format!("Feedback with id {} not found", id))
             }
-            _ => ApiError::DatabaseError(err.into()),
+            _ => ApiError::DatabaseError(err),
         })?;
 
-        let feedback = Feedback {
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(Feedback {
             id: feedback_row.get("id"),
             tenant_id: feedback_row.get("tenant_id"),
             property_image: feedback_row.get("property_image"),
@@ -174,24 +321,82 @@ impl DBRepository for PostgresRepository {
             customer_name: feedback_row.get("customer_name"),
             customer_review: feedback_row.get("customer_review"),
             description: feedback_row.get("description"),
-        };
+            updated_at: feedback_row.get("updated_at"),
+            deleted_at: feedback_row.get("deleted_at"),
+        })
+    }
 
-        // Delete feedback
-        let result = sqlx::query("DELETE FROM feedback WHERE id = $1 AND tenant_id = $2")
-            .bind(id)
-            .bind(tenant_id)
-            .execute(&mut *tx)
+    async fn restore(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        max_feedback_entries: Option<u32>,
+    ) -> Result<Feedback, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-        if result.rows_affected() == 0 {
-            return Err(ApiError::NotFound(
-                "Feedback not found or tenant mismatch".to_string(),
-            ));
-        }
+        reserve_feedback(&mut tx, tenant_id, max_feedback_entries).await?;
+
+        let restored_feedback = sqlx::query(
+            r#"
+            UPDATE feedback
+            SET deleted_at = NULL
+            WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NOT NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::NotFound(format!("Feedback with id {} not found in trash", id))
+            }
+            _ => ApiError::DatabaseError(err),
+        })?;
 
         tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        Ok(feedback)
+        Ok(Feedback {
+            id: restored_feedback.get("id"),
+            tenant_id: restored_feedback.get("tenant_id"),
+            property_image: restored_feedback.get("property_image"),
+            customer_image: restored_feedback.get("customer_image"),
+            customer_name: restored_feedback.get("customer_name"),
+            customer_review: restored_feedback.get("customer_review"),
+            description: restored_feedback.get("description"),
+            updated_at: restored_feedback.get("updated_at"),
+            deleted_at: restored_feedback.get("deleted_at"),
+        })
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<Feedback>, ApiError> {
+        let rows = sqlx::query("DELETE FROM feedback WHERE deleted_at < $1 RETURNING *")
+            .bind(deleted_before)
+            .fetch_all(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Feedback {
+                id: row.get("id"),
+                tenant_id: row.get("tenant_id"),
+                property_image: row.get("property_image"),
+                customer_image: row.get("customer_image"),
+                customer_name: row.get("customer_name"),
+                customer_review: row.get("customer_review"),
+                description: row.get("description"),
+                updated_at: row.get("updated_at"),
+                deleted_at: row.get("deleted_at"),
+            })
+            .collect())
     }
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -10,6 +11,8 @@ pub struct Feedback {
     pub customer_name: String,
     pub customer_review: String,
     pub description: String,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 impl Feedback {
     pub fn new(
@@ -28,6 +31,8 @@ impl Feedback {
             customer_name: customer_name.into(),
             customer_review: customer_review.into(),
             description: description.into(),
+            updated_at: None,
+            deleted_at: None,
         }
     }
 }
@@ -1,26 +1,57 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 
 use crate::{
     error::ApiError,
-    utils::database::{Filter, PaginatedRecord, Pagination},
+    utils::{
+        database::{Filter, PaginatedRecord, Pagination},
+        etag::Precondition,
+    },
 };
 
 use super::Feedback;
 
 #[async_trait]
 pub trait DBRepository: Send + Sync {
-    async fn create(&self, feedback: Feedback) -> Result<Feedback, ApiError>;
-    async fn edit(&self, feedback: Feedback) -> Result<Feedback, ApiError>;
+    /// Inserts the feedback unless the tenant already has `max_feedback_entries`.
+    async fn create(
+        &self,
+        feedback: Feedback,
+        max_feedback_entries: Option<u32>,
+    ) -> Result<Feedback, ApiError>;
+    async fn edit(
+        &self,
+        feedback: Feedback,
+        precondition: &Precondition,
+    ) -> Result<Feedback, ApiError>;
+    async fn find(&self, filter: Filter) -> Result<Feedback, ApiError>;
     async fn find_many(
         &self,
         filter: Filter,
         pagination: Pagination,
     ) -> Result<PaginatedRecord<Feedback>, ApiError>;
-    async fn delete(&self, id: i32, tenant_id: i32) -> Result<Feedback, ApiError>;
+    fn stream(&self, filter: Filter) -> BoxStream<'static, Result<Feedback, ApiError>>;
+    async fn delete(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+    ) -> Result<Feedback, ApiError>;
+    /// Takes the feedback out of the trash unless the tenant already has `max_feedback_entries`.
+    async fn restore(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        max_feedback_entries: Option<u32>,
+    ) -> Result<Feedback, ApiError>;
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>)
+        -> Result<Vec<Feedback>, ApiError>;
 }
 
 #[async_trait]
 pub trait BucketRepository: Send + Sync {
     async fn post_presigned_url(&self, key: &str) -> Result<String, ApiError>;
+    /// Deletes images given by bucket key or by the URL stored for them.
     async fn delete_images(&self, images: &[String]) -> Result<Vec<String>, ApiError>;
 }
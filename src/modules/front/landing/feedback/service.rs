@@ -1,10 +1,19 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 use uuid::Uuid;
 
 use crate::{
     error::ApiError,
-    utils::database::{Filter, FilterCondition, PaginatedRecord, Pagination},
+    modules::{
+        audit::{self, AuditEntity},
+        plan,
+    },
+    utils::{
+        database::{Filter, FilterCondition, PaginatedRecord, Pagination},
+        etag::Precondition,
+    },
 };
 
 use super::{
@@ -15,12 +24,21 @@ use super::{
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
     bucket_repo: Arc<dyn BucketRepository>,
+    plan_service: Arc<plan::Service>,
+    audit_service: Arc<audit::Service>,
 }
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>, bucket_repo: Arc<dyn BucketRepository>) -> Self {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        bucket_repo: Arc<dyn BucketRepository>,
+        plan_service: Arc<plan::Service>,
+        audit_service: Arc<audit::Service>,
+    ) -> Self {
         Self {
             db_repo,
             bucket_repo,
+            plan_service,
+            audit_service,
         }
     }
 }
@@ -30,36 +48,165 @@ impl Service {
         let key = format!("/tenant_{}/feedback/image_{}", tenant_id, Uuid::new_v4());
         self.bucket_repo.post_presigned_url(&key).await
     }
-    pub async fn update_feedback(&self, feedback: Feedback) -> Result<Feedback, ApiError> {
-        self.db_repo.edit(feedback).await
+    pub async fn update_feedback(
+        &self,
+        feedback: Feedback,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Feedback, ApiError> {
+        let before = self.find_feedback_by_id(feedback.id).await?;
+        let updated_feedback = self.db_repo.edit(feedback, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                updated_feedback.tenant_id,
+                AuditEntity::Feedback,
+                updated_feedback.id,
+                &before,
+                &updated_feedback,
+            )
+            .await;
+
+        Ok(updated_feedback)
     }
 
-    pub async fn create_feedback(&self, feedback: Feedback) -> Result<Feedback, ApiError> {
-        self.db_repo.create(feedback).await
+    pub async fn find_feedback_by_id(&self, id: i32) -> Result<Feedback, ApiError> {
+        let mut filter = Filter::new();
+        filter.add("id", FilterCondition::eq(id));
+        filter.add("deleted_at", FilterCondition::IsNull);
+        self.db_repo.find(filter).await
+    }
+
+    pub async fn create_feedback(
+        &self,
+        feedback: Feedback,
+        actor_user_id: &str,
+    ) -> Result<Feedback, ApiError> {
+        let limits = self
+            .plan_service
+            .find_tenant_limits(feedback.tenant_id)
+            .await?;
+
+        let created_feedback = self
+            .db_repo
+            .create(feedback, limits.max_feedback_entries)
+            .await?;
+        self.audit_service
+            .record_create(
+                actor_user_id,
+                created_feedback.tenant_id,
+                AuditEntity::Feedback,
+                created_feedback.id,
+                &created_feedback,
+            )
+            .await;
+
+        Ok(created_feedback)
     }
 
     pub async fn find_tenant_feedback(
         &self,
         tenant_id: i32,
         pagination: Pagination,
+    ) -> Result<PaginatedRecord<Feedback>, ApiError> {
+        self.db_repo
+            .find_many(tenant_feedback_filter(tenant_id), pagination)
+            .await
+    }
+
+    /// Streams the same feedback as `find_tenant_feedback`, unpaginated.
+    pub fn export_tenant_feedback(
+        &self,
+        tenant_id: i32,
+    ) -> BoxStream<'static, Result<Feedback, ApiError>> {
+        self.db_repo.stream(tenant_feedback_filter(tenant_id))
+    }
+
+    pub async fn find_tenant_trash(
+        &self,
+        tenant_id: i32,
+        pagination: Pagination,
     ) -> Result<PaginatedRecord<Feedback>, ApiError> {
         let mut filter = Filter::new();
         filter.add("tenant_id", FilterCondition::eq(tenant_id));
+        filter.add("deleted_at", FilterCondition::IsNotNull);
         self.db_repo.find_many(filter, pagination).await
     }
 
-    pub async fn delete_feedback(&self, id: i32, tenant_id: i32) -> Result<Feedback, ApiError> {
-        let deleted_feedback = self.db_repo.delete(id, tenant_id).await?;
-        let images_to_delete = vec![
-            deleted_feedback.customer_image.clone(),
-            deleted_feedback.property_image.clone(),
-        ];
+    /// Moves the feedback to the trash. Its images are kept until it is purged.
+    pub async fn delete_feedback(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Feedback, ApiError> {
+        let deleted_feedback = self.db_repo.delete(id, tenant_id, precondition).await?;
+        self.audit_service
+            .record_delete(
+                actor_user_id,
+                tenant_id,
+                AuditEntity::Feedback,
+                id,
+                &deleted_feedback,
+            )
+            .await;
+
+        Ok(deleted_feedback)
+    }
+
+    pub async fn restore_feedback(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        actor_user_id: &str,
+    ) -> Result<Feedback, ApiError> {
+        let limits = self.plan_service.find_tenant_limits(tenant_id).await?;
+
+        let restored_feedback = self
+            .db_repo
+            .restore(id, tenant_id, limits.max_feedback_entries)
+            .await?;
+        self.audit_service
+            .record_restore(
+                actor_user_id,
+                tenant_id,
+                AuditEntity::Feedback,
+                id,
+                &restored_feedback,
+            )
+            .await;
+
+        Ok(restored_feedback)
+    }
+
+    /// Permanently removes feedback trashed before `deleted_before`, along with its images.
+    pub async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize, ApiError> {
+        let purged_feedback = self.db_repo.purge_deleted(deleted_before).await?;
+        let images_to_delete: Vec<String> = purged_feedback
+            .iter()
+            .flat_map(|feedback| {
+                [
+                    feedback.customer_image.clone(),
+                    feedback.property_image.clone(),
+                ]
+            })
+            .collect();
 
         //Todo: send the errors to a queue
-        if let Err(e) = self.bucket_repo.delete_images(&images_to_delete).await {
-            eprintln!("Failed to delete images from bucket: {:?}", e);
+        if !images_to_delete.is_empty() {
+            if let Err(e) = self.bucket_repo.delete_images(&images_to_delete).await {
+                log::error!("Failed to delete images from bucket: {:?}", e);
+            }
         }
 
-        Ok(deleted_feedback)
+        Ok(purged_feedback.len())
     }
 }
+
+fn tenant_feedback_filter(tenant_id: i32) -> Filter {
+    let mut filter = Filter::new();
+    filter.add("tenant_id", FilterCondition::eq(tenant_id));
+    filter.add("deleted_at", FilterCondition::IsNull);
+    filter
+}
@@ -1,23 +1,45 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{http::header::ETag, web, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
     error::ApiError,
     modules::{
-        front::landing::hero::{Hero, Service},
+        front::landing::hero::{Hero, HeroPatch, Service},
         tenant,
     },
-    utils::lucia,
+    utils::{
+        etag::{entity_tag, Precondition},
+        lucia,
+        patch::non_null,
+    },
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct UpdateHero {
+    #[validate(length(min = 1, max = 255))]
     pub title: String,
+    #[validate(length(min = 1))]
     pub description: String,
+    #[validate(length(min = 1, max = 255))]
     pub image: String,
 }
 
+/// JSON merge patch body: absent members are left unchanged.
+#[derive(Deserialize, Validate)]
+pub struct PatchHero {
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1))]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 255))]
+    pub image: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct PresignedUrlResponse {
     pub url: String,
@@ -30,6 +52,8 @@ pub async fn update_hero(
     req: web::Json<UpdateHero>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -48,9 +72,52 @@ pub async fn update_hero(
 
     let hero = Hero::new(tenant.id, &req.title, &req.description, &req.image);
 
-    let updated_hero = service.update_hero(hero).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let updated_hero = service
+        .update_hero(hero, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(updated_hero.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(updated_hero))
+}
+
+pub async fn patch_hero(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Json(req): web::Json<PatchHero>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
 
-    Ok(HttpResponse::Ok().json(updated_hero))
+    let patch = HeroPatch {
+        title: req.title,
+        description: req.description,
+        image: req.image,
+    };
+
+    let precondition = Precondition::from_request(&req_headers)?;
+    let patched_hero = service
+        .patch_hero(tenant.id, patch, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(patched_hero.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(patched_hero))
 }
 
 pub async fn get_tenant_hero(
@@ -58,7 +125,12 @@ pub async fn get_tenant_hero(
     tenant_id: web::Path<i32>,
 ) -> Result<HttpResponse, ApiError> {
     let hero = service.find_tenant_hero(*tenant_id).await?;
-    Ok(HttpResponse::Ok().json(hero))
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(hero.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(hero))
 }
 
 pub async fn generate_hero_image_presigned_url(
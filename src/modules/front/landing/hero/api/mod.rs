@@ -1,5 +1,5 @@
 use actix_web::web;
-use handler::{generate_hero_image_presigned_url, get_tenant_hero, update_hero};
+use handler::{generate_hero_image_presigned_url, get_tenant_hero, patch_hero, update_hero};
 
 mod handler;
 
@@ -7,6 +7,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/hero")
             .route("", web::put().to(update_hero))
+            .route("", web::patch().to(patch_hero))
             .route("/tenant/{tenant_id}", web::get().to(get_tenant_hero))
             .route(
                 "/image-upload-url",
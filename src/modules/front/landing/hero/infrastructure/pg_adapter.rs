@@ -1,14 +1,41 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::Row;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 
 use crate::error::ApiError;
 use crate::modules::front::landing::hero::port::DBRepository;
-use crate::modules::front::landing::hero::Hero;
-use crate::utils::database::{Filter, PostgresRepository, Value};
+use crate::modules::front::landing::hero::{Hero, HeroPatch};
+use crate::utils::database::{push_set, Filter, PostgresRepository, Value};
+use crate::utils::etag::Precondition;
+
+/// Locks the tenant's hero row for the rest of the transaction and returns its version.
+async fn lock_hero(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+) -> Result<Option<DateTime<Utc>>, ApiError> {
+    sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT updated_at FROM hero WHERE tenant_id = $1 FOR UPDATE",
+    )
+    .bind(tenant_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(ApiError::DatabaseError)?
+    .ok_or_else(|| ApiError::NotFound(format!("Hero for tenant_id {} not found", tenant_id)))
+}
 
 #[async_trait]
 impl DBRepository for PostgresRepository {
-    async fn edit(&self, hero: Hero) -> Result<Hero, ApiError> {
+    async fn edit(&self, hero: Hero, precondition: &Precondition) -> Result<Hero, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_hero(&mut tx, hero.tenant_id).await?;
+        precondition.ensure(current_version)?;
+
         let updated_hero = sqlx::query(
             r#"
             UPDATE hero
@@ -21,7 +48,7 @@ impl DBRepository for PostgresRepository {
         .bind(&hero.description)
         .bind(&hero.image)
         .bind(&hero.tenant_id)
-        .fetch_one(&*self.pg_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => {
@@ -30,12 +57,65 @@ impl DBRepository for PostgresRepository {
             _ => ApiError::DatabaseError(e),
         })?;
 
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
         Ok(Hero {
             id: updated_hero.get("id"),
             tenant_id: updated_hero.get("tenant_id"),
             title: updated_hero.get("title"),
             description: updated_hero.get("description"),
             image: updated_hero.get("image"),
+            updated_at: updated_hero.get("updated_at"),
+        })
+    }
+
+    async fn patch(
+        &self,
+        tenant_id: i32,
+        patch: HeroPatch,
+        precondition: &Precondition,
+    ) -> Result<Hero, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_hero(&mut tx, tenant_id).await?;
+        precondition.ensure(current_version)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE hero SET ");
+        let mut set = query.separated(", ");
+        push_set(&mut set, "title", patch.title);
+        push_set(&mut set, "description", patch.description);
+        push_set(&mut set, "image", patch.image);
+        set.push("updated_at = CURRENT_TIMESTAMP");
+
+        query
+            .push(" WHERE tenant_id = ")
+            .push_bind(tenant_id)
+            .push(" RETURNING *");
+
+        let patched_hero = query
+            .build()
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => {
+                    ApiError::NotFound(format!("Hero for tenant_id {} not found", tenant_id))
+                }
+                _ => ApiError::DatabaseError(e),
+            })?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(Hero {
+            id: patched_hero.get("id"),
+            tenant_id: patched_hero.get("tenant_id"),
+            title: patched_hero.get("title"),
+            description: patched_hero.get("description"),
+            image: patched_hero.get("image"),
+            updated_at: patched_hero.get("updated_at"),
         })
     }
 
@@ -70,6 +150,7 @@ impl DBRepository for PostgresRepository {
             title: hero.get("title"),
             description: hero.get("description"),
             image: hero.get("image"),
+            updated_at: hero.get("updated_at"),
         })
     }
 }
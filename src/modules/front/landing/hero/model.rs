@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
@@ -8,6 +9,7 @@ pub struct Hero {
     pub title: String,
     pub description: String,
     pub image: String,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 impl Hero {
     pub fn new(tenant_id: i32, title: &str, description: &str, image: &str) -> Self {
@@ -17,6 +19,15 @@ impl Hero {
             title: title.into(),
             description: description.into(),
             image: image.into(),
+            updated_at: None,
         }
     }
 }
+
+/// Columns to change on a hero; `None` leaves a column untouched.
+#[derive(Debug, Default)]
+pub struct HeroPatch {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
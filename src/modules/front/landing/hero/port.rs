@@ -1,12 +1,21 @@
 use async_trait::async_trait;
 
-use crate::{error::ApiError, utils::database::Filter};
+use crate::{
+    error::ApiError,
+    utils::{database::Filter, etag::Precondition},
+};
 
-use super::Hero;
+use super::{Hero, HeroPatch};
 
 #[async_trait]
 pub trait DBRepository: Send + Sync {
-    async fn edit(&self, hero: Hero) -> Result<Hero, ApiError>;
+    async fn edit(&self, hero: Hero, precondition: &Precondition) -> Result<Hero, ApiError>;
+    async fn patch(
+        &self,
+        tenant_id: i32,
+        patch: HeroPatch,
+        precondition: &Precondition,
+    ) -> Result<Hero, ApiError>;
     async fn find(&self, fileter: Filter) -> Result<Hero, ApiError>;
 }
 
@@ -4,23 +4,33 @@ use uuid::Uuid;
 
 use crate::{
     error::ApiError,
-    utils::database::{Filter, FilterCondition},
+    modules::audit::{self, AuditEntity},
+    utils::{
+        database::{Filter, FilterCondition},
+        etag::Precondition,
+    },
 };
 
 use super::{
     port::{BucketRepository, DBRepository},
-    Hero,
+    Hero, HeroPatch,
 };
 
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
     bucket_repo: Arc<dyn BucketRepository>,
+    audit_service: Arc<audit::Service>,
 }
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>, bucket_repo: Arc<dyn BucketRepository>) -> Self {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        bucket_repo: Arc<dyn BucketRepository>,
+        audit_service: Arc<audit::Service>,
+    ) -> Self {
         Self {
             db_repo,
             bucket_repo,
+            audit_service,
         }
     }
 }
@@ -31,8 +41,49 @@ impl Service {
         self.bucket_repo.post_presigned_url(&key).await
     }
 
-    pub async fn update_hero(&self, config: Hero) -> Result<Hero, ApiError> {
-        self.db_repo.edit(config).await
+    pub async fn update_hero(
+        &self,
+        config: Hero,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Hero, ApiError> {
+        let before = self.find_tenant_hero(config.tenant_id).await?;
+        let updated = self.db_repo.edit(config, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                updated.tenant_id,
+                AuditEntity::Hero,
+                updated.id,
+                &before,
+                &updated,
+            )
+            .await;
+
+        Ok(updated)
+    }
+
+    pub async fn patch_hero(
+        &self,
+        tenant_id: i32,
+        patch: HeroPatch,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Hero, ApiError> {
+        let before = self.find_tenant_hero(tenant_id).await?;
+        let patched = self.db_repo.patch(tenant_id, patch, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                tenant_id,
+                AuditEntity::Hero,
+                patched.id,
+                &before,
+                &patched,
+            )
+            .await;
+
+        Ok(patched)
     }
 
     pub async fn find_tenant_hero(&self, tenant_id: i32) -> Result<Hero, ApiError> {
@@ -1,20 +1,42 @@
 use actix_web::{web, HttpRequest, HttpResponse};
 use serde::Deserialize;
 use std::sync::Arc;
+use validator::{Validate, ValidationError};
 
 use crate::error::ApiError;
 use crate::modules::front::social_media::{Service, SocialMedia};
 use crate::modules::tenant::Service as TenantService;
 use crate::utils::lucia::Service as LuciaService;
+use crate::utils::validation::validate_url_domain;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct UpsertSocialMediaRequest {
+    #[validate(length(max = 255), custom(function = "validate_facebook_url"))]
     pub facebook_url: Option<String>,
+    #[validate(length(max = 255), custom(function = "validate_instagram_url"))]
     pub instagram_url: Option<String>,
+    #[validate(length(max = 255), custom(function = "validate_tiktok_url"))]
     pub tiktok_url: Option<String>,
+    #[validate(length(max = 255), custom(function = "validate_linkedin_url"))]
     pub linkedin_url: Option<String>,
 }
 
+fn validate_facebook_url(url: &str) -> Result<(), ValidationError> {
+    validate_url_domain(url, "facebook.com")
+}
+
+fn validate_instagram_url(url: &str) -> Result<(), ValidationError> {
+    validate_url_domain(url, "instagram.com")
+}
+
+fn validate_tiktok_url(url: &str) -> Result<(), ValidationError> {
+    validate_url_domain(url, "tiktok.com")
+}
+
+fn validate_linkedin_url(url: &str) -> Result<(), ValidationError> {
+    validate_url_domain(url, "linkedin.com")
+}
+
 pub async fn upsert_social_media(
     service: web::Data<Arc<Service>>,
     tenant_service: web::Data<Arc<TenantService>>,
@@ -22,6 +44,8 @@ pub async fn upsert_social_media(
     req: web::Json<UpsertSocialMediaRequest>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -46,7 +70,9 @@ pub async fn upsert_social_media(
         linkedin_url: req.linkedin_url.clone(),
     };
 
-    let upserted_social_media = service.upsert(social_media, tenant.id).await?;
+    let upserted_social_media = service
+        .upsert(social_media, tenant.id, &session.user_id)
+        .await?;
     Ok(HttpResponse::Ok().json(upserted_social_media))
 }
 
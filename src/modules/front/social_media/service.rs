@@ -1,23 +1,64 @@
 use std::sync::Arc;
 
-use crate::error::ApiError;
+use crate::{
+    error::ApiError,
+    modules::audit::{self, AuditEntity},
+};
 
 use super::{port::DBRepository, SocialMedia};
 
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
+    audit_service: Arc<audit::Service>,
 }
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>) -> Self {
-        Self { db_repo }
+    pub fn new(db_repo: Arc<dyn DBRepository>, audit_service: Arc<audit::Service>) -> Self {
+        Self {
+            db_repo,
+            audit_service,
+        }
     }
 
     pub async fn upsert(
         &self,
         social_media: SocialMedia,
         tenant_id: i32,
+        actor_user_id: &str,
     ) -> Result<SocialMedia, ApiError> {
-        self.db_repo.upsert(social_media, tenant_id).await
+        let before = match self.db_repo.find(tenant_id).await {
+            Ok(before) => Some(before),
+            Err(ApiError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let upserted = self.db_repo.upsert(social_media, tenant_id).await?;
+
+        match before {
+            Some(before) => {
+                self.audit_service
+                    .record_update(
+                        actor_user_id,
+                        tenant_id,
+                        AuditEntity::SocialMedia,
+                        upserted.id,
+                        &before,
+                        &upserted,
+                    )
+                    .await
+            }
+            None => {
+                self.audit_service
+                    .record_create(
+                        actor_user_id,
+                        tenant_id,
+                        AuditEntity::SocialMedia,
+                        upserted.id,
+                        &upserted,
+                    )
+                    .await
+            }
+        }
+
+        Ok(upserted)
     }
 
     pub async fn find(&self, tenant_id: i32) -> Result<SocialMedia, ApiError> {
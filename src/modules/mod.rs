@@ -1,4 +1,9 @@
+pub mod audit;
+pub mod feed;
 pub mod front;
+pub mod plan;
 pub mod property;
+pub mod report;
+pub mod site;
 pub mod stats;
 pub mod tenant;
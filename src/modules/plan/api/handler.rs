@@ -0,0 +1,27 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::sync::Arc;
+
+use crate::{
+    error::ApiError,
+    modules::{plan::Service, tenant},
+    utils::lucia,
+};
+
+pub async fn get_plan_usage(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let plan_usage = service.find_tenant_plan_usage(tenant.id).await?;
+    Ok(HttpResponse::Ok().json(plan_usage))
+}
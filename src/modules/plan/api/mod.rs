@@ -0,0 +1,8 @@
+use actix_web::web;
+use handler::get_plan_usage;
+
+mod handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/plan").route("/usage", web::get().to(get_plan_usage)));
+}
@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, PgConnection, Row};
+
+use crate::{
+    error::ApiError,
+    modules::plan::{port::DBRepository, Plan, Usage},
+    utils::database::PostgresRepository,
+};
+
+const USAGE_QUERY: &str = r#"
+    SELECT
+        (SELECT COUNT(*) FROM properties WHERE tenant_id = $1 AND deleted_at IS NULL) AS active_listings,
+        (SELECT COUNT(*) FROM feedback WHERE tenant_id = $1 AND deleted_at IS NULL) AS feedback_entries
+"#;
+
+fn usage_from_row(row: PgRow) -> Usage {
+    Usage {
+        active_listings: row.get::<i64, _>("active_listings") as u32,
+        feedback_entries: row.get::<i64, _>("feedback_entries") as u32,
+    }
+}
+
+/// Locks the tenant until the transaction ends and returns its usage, so
+/// concurrent creates wait for each other and cannot overrun a limit.
+pub async fn lock_usage(conn: &mut PgConnection, tenant_id: i32) -> Result<Usage, ApiError> {
+    // NO KEY UPDATE leaves inserts referencing the tenant unblocked
+    sqlx::query("SELECT id FROM tenants WHERE id = $1 FOR NO KEY UPDATE")
+        .bind(tenant_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(ApiError::DatabaseError)?
+        .ok_or_else(|| ApiError::NotFound(format!("Tenant with id {} not found", tenant_id)))?;
+
+    let row = sqlx::query(USAGE_QUERY)
+        .bind(tenant_id)
+        .fetch_one(conn)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+    Ok(usage_from_row(row))
+}
+
+#[async_trait]
+impl DBRepository for PostgresRepository {
+    async fn find_plan(&self, tenant_id: i32) -> Result<Plan, ApiError> {
+        let premium = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT COALESCE(u.premium, FALSE)
+            FROM tenants t
+            JOIN auth_user u ON u.id = t.auth_user_id
+            WHERE t.id = $1
+            "#,
+        )
+        .bind(tenant_id)
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::NotFound(format!("Tenant with id {} not found", tenant_id))
+            }
+            _ => ApiError::DatabaseError(err),
+        })?;
+
+        Ok(Plan::from_premium(premium))
+    }
+
+    async fn find_usage(&self, tenant_id: i32) -> Result<Usage, ApiError> {
+        let row = sqlx::query(USAGE_QUERY)
+            .bind(tenant_id)
+            .fetch_one(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        Ok(usage_from_row(row))
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Plan {
+    Free,
+    Premium,
+}
+
+impl Plan {
+    pub fn from_premium(premium: bool) -> Self {
+        if premium {
+            Plan::Premium
+        } else {
+            Plan::Free
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        match self {
+            Plan::Free => Limits {
+                max_active_listings: Some(10),
+                max_images_per_listing: Some(10),
+                max_feedback_entries: Some(5),
+            },
+            Plan::Premium => Limits {
+                max_active_listings: None,
+                max_images_per_listing: Some(50),
+                max_feedback_entries: None,
+            },
+        }
+    }
+}
+
+/// Entitlements of a plan. `None` means unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_active_listings: Option<u32>,
+    pub max_images_per_listing: Option<u32>,
+    pub max_feedback_entries: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub active_listings: u32,
+    pub feedback_entries: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUsage {
+    pub tenant_id: i32,
+    pub plan: Plan,
+    pub limits: Limits,
+    pub usage: Usage,
+}
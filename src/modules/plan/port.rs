@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use crate::error::ApiError;
+
+use super::{Plan, Usage};
+
+#[async_trait]
+pub trait DBRepository: Send + Sync {
+    async fn find_plan(&self, tenant_id: i32) -> Result<Plan, ApiError>;
+    async fn find_usage(&self, tenant_id: i32) -> Result<Usage, ApiError>;
+}
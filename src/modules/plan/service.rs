@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::error::ApiError;
+
+use super::{port::DBRepository, Limits, PlanUsage};
+
+pub struct Service {
+    db_repo: Arc<dyn DBRepository>,
+}
+
+impl Service {
+    pub fn new(db_repo: Arc<dyn DBRepository>) -> Self {
+        Self { db_repo }
+    }
+
+    pub async fn find_tenant_plan_usage(&self, tenant_id: i32) -> Result<PlanUsage, ApiError> {
+        let plan = self.db_repo.find_plan(tenant_id).await?;
+        let usage = self.db_repo.find_usage(tenant_id).await?;
+
+        Ok(PlanUsage {
+            tenant_id,
+            plan,
+            limits: plan.limits(),
+            usage,
+        })
+    }
+
+    /// The plan's limits. Limits on counts are enforced by the repositories
+    /// while inserting, under `infrastructure::lock_usage`.
+    pub async fn find_tenant_limits(&self, tenant_id: i32) -> Result<Limits, ApiError> {
+        Ok(self.db_repo.find_plan(tenant_id).await?.limits())
+    }
+
+    /// Fails when the tenant cannot publish `listings` more listings with up to `images` images each.
+    /// This is only an early answer; the listing count is checked again when inserting.
+    pub async fn ensure_listings_available(
+        &self,
+        tenant_id: i32,
+        listings: usize,
+        images: usize,
+    ) -> Result<(), ApiError> {
+        let plan_usage = self.find_tenant_plan_usage(tenant_id).await?;
+        check_limit(
+            "max_active_listings",
+            plan_usage.limits.max_active_listings,
+            plan_usage.usage.active_listings as usize + listings,
+        )?;
+        check_limit(
+            "max_images_per_listing",
+            plan_usage.limits.max_images_per_listing,
+            images,
+        )
+    }
+
+    pub async fn ensure_images_allowed(
+        &self,
+        tenant_id: i32,
+        images: usize,
+    ) -> Result<(), ApiError> {
+        let plan = self.db_repo.find_plan(tenant_id).await?;
+        check_limit(
+            "max_images_per_listing",
+            plan.limits().max_images_per_listing,
+            images,
+        )
+    }
+}
+
+/// Fails when `requested` goes past `limit`, reported under the limit's `name`.
+pub fn check_limit(name: &str, limit: Option<u32>, requested: usize) -> Result<(), ApiError> {
+    match limit {
+        Some(limit) if requested > limit as usize => {
+            Err(ApiError::PlanLimitExceeded(name.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::{
+    error::ApiError,
+    modules::{
+        property::{PropertyWithImages, Service},
+        tenant,
+    },
+    utils::{
+        export::{export_response, CsvRecord, ExportQuery},
+        lucia,
+    },
+};
+
+/// Columns match the importer so an export can be imported back as is; the
+/// importer also drops the quote put before text that looks like a formula.
+impl CsvRecord for PropertyWithImages {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "id",
+            "title",
+            "description",
+            "property_type",
+            "status",
+            "price",
+            "currency",
+            "bedrooms",
+            "bathrooms",
+            "parking_spaces",
+            "total_area",
+            "built_area",
+            "year_built",
+            "address",
+            "city",
+            "state",
+            "country",
+            "google_maps_url",
+            "amenities",
+            "images_urls",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        fn optional<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+
+        let property = &self.property;
+        let images_urls: Vec<&str> = self
+            .images
+            .iter()
+            .map(|image| image.image_url.as_str())
+            .collect();
+
+        vec![
+            property.id.to_string(),
+            property.title.clone(),
+            optional(&property.description),
+            property.property_type.clone(),
+            property.status.clone(),
+            property.price.to_string(),
+            property.currency.clone(),
+            optional(&property.bedrooms),
+            optional(&property.bathrooms),
+            optional(&property.parking_spaces),
+            optional(&property.total_area),
+            optional(&property.built_area),
+            optional(&property.year_built),
+            optional(&property.address),
+            optional(&property.city),
+            optional(&property.state),
+            optional(&property.country),
+            optional(&property.google_maps_url),
+            property
+                .amenities
+                .as_ref()
+                .map(|amenities| amenities.join(";"))
+                .unwrap_or_default(),
+            images_urls.join(";"),
+        ]
+    }
+}
+
+pub async fn export_properties(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    query: web::Query<ExportQuery>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let properties = service.export_tenant_properties(tenant.id);
+    Ok(export_response(properties, query.format, "properties"))
+}
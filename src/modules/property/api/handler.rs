@@ -1,61 +1,226 @@
 use std::sync::Arc;
 
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{
+    http::header::{self, ETag},
+    web, HttpRequest, HttpResponse,
+};
 use serde::Deserialize;
+use validator::Validate;
 
 use crate::{
     error::ApiError,
     modules::{
-        property::{Property, Service},
+        property::{Property, PropertyPatch, Service, SlugLookup},
         tenant,
     },
-    utils::{database::Pagination, lucia},
+    utils::{
+        database::Pagination,
+        etag::{entity_tag, Precondition},
+        lucia,
+        patch::{non_null, nullable},
+        validation::{validate_finite, validate_slug},
+    },
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct CreateProperty {
+    #[validate(length(min = 1, max = 255))]
     pub title: String,
     pub description: Option<String>,
+    #[validate(length(min = 1, max = 50))]
     pub property_type: String,
+    #[validate(length(min = 1, max = 50))]
     pub status: String,
+    #[validate(range(min = 0.0), custom(function = "validate_finite"))]
     pub price: f64,
+    #[validate(length(equal = 3))]
     pub currency: String,
+    #[validate(range(min = 0))]
     pub bedrooms: Option<i32>,
+    #[validate(range(min = 0))]
     pub bathrooms: Option<i32>,
+    #[validate(range(min = 0))]
     pub parking_spaces: Option<i32>,
+    #[validate(range(min = 0.0), custom(function = "validate_finite"))]
     pub total_area: Option<f64>,
+    #[validate(range(min = 0.0), custom(function = "validate_finite"))]
     pub built_area: Option<f64>,
+    #[validate(range(min = 1800, max = 2100))]
     pub year_built: Option<i32>,
+    #[validate(length(max = 255))]
     pub address: Option<String>,
+    #[validate(length(max = 100))]
     pub city: Option<String>,
+    #[validate(length(max = 100))]
     pub state: Option<String>,
+    #[validate(length(max = 100))]
     pub country: Option<String>,
+    #[validate(url)]
     pub google_maps_url: Option<String>,
     pub amenities: Option<Vec<String>>,
     pub images_urls: Vec<String>,
+    #[validate(length(max = 100), custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+    #[validate(length(min = 1, max = 70))]
+    pub meta_title: Option<String>,
+    #[validate(length(min = 1, max = 160))]
+    pub meta_description: Option<String>,
+    #[validate(url)]
+    pub og_image: Option<String>,
+}
+
+impl CreateProperty {
+    pub(super) fn to_property(&self, tenant_id: i32) -> Property {
+        let property = Property::new(
+            tenant_id,
+            &self.title,
+            self.description.as_deref(),
+            &self.property_type,
+            &self.status,
+            self.price,
+            &self.currency,
+            self.bedrooms,
+            self.bathrooms,
+            self.parking_spaces,
+            self.total_area,
+            self.built_area,
+            self.year_built,
+            self.address.as_deref(),
+            self.city.as_deref(),
+            self.state.as_deref(),
+            self.country.as_deref(),
+            self.amenities.clone(),
+            self.google_maps_url.as_deref(),
+        );
+
+        Property {
+            slug: self.slug.clone(),
+            meta_title: self.meta_title.clone(),
+            meta_description: self.meta_description.clone(),
+            og_image: self.og_image.clone(),
+            ..property
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct UpdateProperty {
+    #[validate(length(min = 1, max = 255))]
     pub title: String,
     pub description: Option<String>,
+    #[validate(length(min = 1, max = 50))]
     pub property_type: String,
+    #[validate(length(min = 1, max = 50))]
     pub status: String,
+    #[validate(range(min = 0.0))]
     pub price: f64,
+    #[validate(length(equal = 3))]
     pub currency: String,
+    #[validate(range(min = 0))]
     pub bedrooms: Option<i32>,
+    #[validate(range(min = 0))]
     pub bathrooms: Option<i32>,
+    #[validate(range(min = 0))]
     pub parking_spaces: Option<i32>,
+    #[validate(range(min = 0.0))]
     pub total_area: Option<f64>,
+    #[validate(range(min = 0.0))]
     pub built_area: Option<f64>,
+    #[validate(range(min = 1800, max = 2100))]
     pub year_built: Option<i32>,
+    #[validate(length(max = 255))]
     pub address: Option<String>,
+    #[validate(length(max = 100))]
     pub city: Option<String>,
+    #[validate(length(max = 100))]
     pub state: Option<String>,
+    #[validate(length(max = 100))]
     pub country: Option<String>,
+    #[validate(url)]
     pub google_maps_url: Option<String>,
     pub amenities: Option<Vec<String>>,
     pub images: Vec<String>,
+    /// When omitted the slug follows title and city changes; the other SEO fields
+    /// fall back to generated values.
+    #[validate(length(max = 100), custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+    #[validate(length(min = 1, max = 70))]
+    pub meta_title: Option<String>,
+    #[validate(length(min = 1, max = 160))]
+    pub meta_description: Option<String>,
+    #[validate(url)]
+    pub og_image: Option<String>,
+}
+
+/// JSON merge patch body: absent members are left unchanged, `null` clears.
+#[derive(Deserialize, Validate)]
+pub struct PatchProperty {
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 50))]
+    pub property_type: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 50))]
+    pub status: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(range(min = 0.0))]
+    pub price: Option<f64>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 0))]
+    pub bedrooms: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 0))]
+    pub bathrooms: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 0))]
+    pub parking_spaces: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 0.0))]
+    pub total_area: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 0.0))]
+    pub built_area: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(range(min = 1800, max = 2100))]
+    pub year_built: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(max = 255))]
+    pub address: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(max = 100))]
+    pub city: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(max = 100))]
+    pub state: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(max = 100))]
+    pub country: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(url)]
+    pub google_maps_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub amenities: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub images: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(max = 100), custom(function = "validate_slug"))]
+    pub slug: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(min = 1, max = 70))]
+    pub meta_title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(min = 1, max = 160))]
+    pub meta_description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(url)]
+    pub og_image: Option<Option<String>>,
 }
 
 pub async fn create_property(
@@ -65,6 +230,8 @@ pub async fn create_property(
     req: web::Json<CreateProperty>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -80,28 +247,10 @@ pub async fn create_property(
         .await?;
 
     let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
-    let property = Property::new(
-        tenant.id,
-        &req.title,
-        req.description.as_deref(),
-        &req.property_type,
-        &req.status,
-        req.price,
-        &req.currency,
-        req.bedrooms,
-        req.bathrooms,
-        req.parking_spaces,
-        req.total_area,
-        req.built_area,
-        req.year_built,
-        req.address.as_deref(),
-        req.city.as_deref(),
-        req.state.as_deref(),
-        req.country.as_deref(),
-        req.amenities.clone(),
-        req.google_maps_url.as_deref(),
-    );
-    let property_with_images = service.create(property, &req.images_urls).await?;
+    let property = req.to_property(tenant.id);
+    let property_with_images = service
+        .create(property, &req.images_urls, &session.user_id)
+        .await?;
 
     Ok(HttpResponse::Created().json(property_with_images))
 }
@@ -114,6 +263,8 @@ pub async fn update_property(
     req: web::Json<UpdateProperty>,
     req_headers: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let basic_auth_header = req_headers
         .headers()
         .get("Authorization")
@@ -150,18 +301,113 @@ pub async fn update_property(
         country: req.country.clone(),
         google_maps_url: req.google_maps_url.clone(),
         amenities: req.amenities.clone(),
+        slug: req.slug.clone(),
+        meta_title: req.meta_title.clone(),
+        meta_description: req.meta_description.clone(),
+        og_image: req.og_image.clone(),
+        updated_at: None,
+        deleted_at: None,
+    };
+
+    let precondition = Precondition::from_request(&req_headers)?;
+    let updated_property = service
+        .update_property(property, &req.images, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(updated_property.property.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(updated_property))
+}
+
+pub async fn patch_property(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    property_id: web::Path<i32>,
+    web::Json(req): web::Json<PatchProperty>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let patch = PropertyPatch {
+        title: req.title,
+        description: req.description,
+        property_type: req.property_type,
+        status: req.status,
+        price: req.price,
+        currency: req.currency,
+        bedrooms: req.bedrooms,
+        bathrooms: req.bathrooms,
+        parking_spaces: req.parking_spaces,
+        total_area: req.total_area,
+        built_area: req.built_area,
+        year_built: req.year_built,
+        address: req.address,
+        city: req.city,
+        state: req.state,
+        country: req.country,
+        google_maps_url: req.google_maps_url,
+        amenities: req.amenities,
+        slug: req.slug,
+        meta_title: req.meta_title,
+        meta_description: req.meta_description,
+        og_image: req.og_image,
     };
+    // `"images": null` removes every image.
+    let images_urls = req.images.map(Option::unwrap_or_default);
 
-    let updated_property = service.update_property(property, &req.images).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let patched_property = service
+        .patch_property(
+            *property_id,
+            tenant.id,
+            patch,
+            images_urls,
+            &precondition,
+            &session.user_id,
+        )
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(patched_property.property.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(patched_property))
+}
 
-    Ok(HttpResponse::Ok().json(updated_property))
+#[derive(Deserialize, Validate)]
+pub struct SearchQuery {
+    /// Free text such as "departamento con piscina en Miraflores".
+    #[validate(length(max = 200))]
+    pub q: Option<String>,
 }
 
 pub async fn get_tenant_properties(
     service: web::Data<Arc<Service>>,
     tenant_id: web::Path<i32>,
     web::Query(pagination): web::Query<Pagination>,
+    web::Query(search): web::Query<SearchQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    search.validate()?;
+
+    if let Some(q) = search.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let hits = service
+            .search_tenant_properties(*tenant_id, q, pagination)
+            .await?;
+        return Ok(HttpResponse::Ok().json(hits));
+    }
+
     let properties = service
         .find_all_tenant_properties(*tenant_id, pagination.into())
         .await?;
@@ -174,11 +420,73 @@ pub async fn get_property_by_id(
     property_id: web::Path<i32>,
 ) -> Result<HttpResponse, ApiError> {
     let property = service.find_property_by_id(*property_id).await?;
-    Ok(HttpResponse::Ok().json(property))
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(property.property.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(property))
+}
+
+/// Current listings are returned as is; former slugs answer `301` pointing at the current one.
+pub async fn get_property_by_slug(
+    service: web::Data<Arc<Service>>,
+    path: web::Path<(i32, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (tenant_id, slug) = path.into_inner();
+
+    match service.find_property_by_slug(tenant_id, &slug).await? {
+        SlugLookup::Found(property) => {
+            let mut response = HttpResponse::Ok();
+            if let Some(etag) = entity_tag(property.property.updated_at) {
+                response.insert_header(ETag(etag));
+            }
+            Ok(response.json(property))
+        }
+        SlugLookup::Moved(redirect) => Ok(HttpResponse::MovedPermanently()
+            .insert_header((
+                header::LOCATION,
+                format!(
+                    "/v2/tenants/{}/properties/by-slug/{}",
+                    tenant_id, redirect.slug
+                ),
+            ))
+            .json(redirect)),
+    }
+}
+
+const DEFAULT_SIMILAR_LIMIT: usize = 6;
+
+#[derive(Deserialize, Validate)]
+pub struct SimilarQuery {
+    #[validate(range(min = 1, max = 20))]
+    pub limit: Option<usize>,
+}
+
+pub async fn get_similar_properties(
+    service: web::Data<Arc<Service>>,
+    property_id: web::Path<i32>,
+    web::Query(query): web::Query<SimilarQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let similar = service
+        .find_similar_properties(*property_id, query.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT))
+        .await?;
+    Ok(HttpResponse::Ok().json(similar))
+}
+
+pub async fn get_property_seo(
+    service: web::Data<Arc<Service>>,
+    property_id: web::Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let property = service.find_property_by_id(*property_id).await?;
+    Ok(HttpResponse::Ok().json(property.seo()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct GeneratePresignedUrls {
+    #[validate(range(min = 1, max = 50))]
     pub n_links: usize,
 }
 
@@ -187,6 +495,8 @@ pub async fn generate_presigned_urls(
     tenant_id: web::Path<i32>,
     req: web::Json<GeneratePresignedUrls>,
 ) -> Result<HttpResponse, ApiError> {
+    req.validate()?;
+
     let urls = service
         .generate_post_presigned_urls(*tenant_id, req.n_links)
         .await?;
@@ -215,6 +525,70 @@ pub async fn delete_property(
         .await?;
 
     let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
-    let deleted_property = service.delete_property(*property_id, tenant.id).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let deleted_property = service
+        .delete_property(*property_id, tenant.id, &precondition, &session.user_id)
+        .await?;
     Ok(HttpResponse::Ok().json(deleted_property))
 }
+
+pub async fn get_trash(
+    service: web::Data<Arc<Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    query: web::Query<Pagination>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok());
+
+    if basic_auth_header.is_none() {
+        return Err(ApiError::Unauthorized(
+            "Missing Authorization header".into(),
+        ));
+    }
+    let session = lucia_service
+        .get_session(basic_auth_header.unwrap())
+        .await?;
+
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+    let trash = service
+        .find_tenant_trash(tenant.id, query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(trash))
+}
+
+pub async fn restore_property(
+    service: web::Data<Arc<Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    property_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok());
+
+    if basic_auth_header.is_none() {
+        return Err(ApiError::Unauthorized(
+            "Missing Authorization header".into(),
+        ));
+    }
+    let session = lucia_service
+        .get_session(basic_auth_header.unwrap())
+        .await?;
+
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+    let restored_property = service
+        .restore_property(*property_id, tenant.id, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(restored_property.property.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(restored_property))
+}
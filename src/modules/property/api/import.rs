@@ -0,0 +1,307 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use validator::{Validate, ValidateUrl};
+
+use crate::{
+    error::ApiError,
+    modules::{
+        property::{ImportRow, ImportRowError, Service},
+        tenant,
+    },
+    utils::{export::restore_formula, lucia},
+};
+
+use super::handler::CreateProperty;
+
+pub const MAX_IMPORT_BYTES: usize = 5 * 1024 * 1024;
+
+/// Columns understood by the importer, named after the `CreateProperty` fields.
+const FIELDS: [&str; 20] = [
+    "title",
+    "description",
+    "property_type",
+    "status",
+    "price",
+    "currency",
+    "bedrooms",
+    "bathrooms",
+    "parking_spaces",
+    "total_area",
+    "built_area",
+    "year_built",
+    "address",
+    "city",
+    "state",
+    "country",
+    "google_maps_url",
+    "amenities",
+    "images_urls",
+    "images",
+];
+
+const REQUIRED_FIELDS: [&str; 5] = ["title", "property_type", "status", "price", "currency"];
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Renames spreadsheet columns to fields, e.g. `Precio:price,Ciudad:city`.
+    pub mapping: Option<String>,
+}
+
+/// Imports listings from a CSV body. Lists (amenities, images_urls) are `;`-separated.
+pub async fn import_properties(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let mapping = parse_mapping(query.mapping.as_deref())?;
+    let (rows, parse_errors, total_rows) = parse_csv(&body, &mapping, tenant.id)?;
+
+    let mut report = service
+        .import_properties(tenant.id, rows, query.dry_run, &session.user_id)
+        .await?;
+    report.total_rows = total_rows;
+    report.errors.extend(parse_errors);
+    report.errors.sort_by_key(|error| error.row);
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Lowercases a header and replaces spaces and dashes so `Property Type` matches `property_type`.
+fn normalize(column: &str) -> String {
+    column.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+fn parse_mapping(mapping: Option<&str>) -> Result<HashMap<String, &'static str>, ApiError> {
+    let Some(mapping) = mapping else {
+        return Ok(HashMap::new());
+    };
+
+    mapping
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (column, field) = pair.split_once(':').ok_or_else(|| {
+                ApiError::InvalidInput("query", format!("Invalid mapping entry {}", pair))
+            })?;
+            let field = FIELDS
+                .iter()
+                .find(|known| **known == normalize(field))
+                .ok_or_else(|| {
+                    ApiError::InvalidInput("query", format!("Unknown field {}", field))
+                })?;
+            Ok((normalize(column), *field))
+        })
+        .collect()
+}
+
+/// Splits the file into importable rows and per-row errors, returning the number of data rows.
+fn parse_csv(
+    body: &[u8],
+    mapping: &HashMap<String, &'static str>,
+    tenant_id: i32,
+) -> Result<(Vec<ImportRow>, Vec<ImportRowError>, usize), ApiError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| ApiError::InvalidInput("csv", e.to_string()))?;
+    let columns: Vec<Option<&'static str>> = headers
+        .iter()
+        .map(|header| {
+            let header = normalize(header);
+            mapping.get(&header).copied().or_else(|| {
+                FIELDS
+                    .iter()
+                    .find(|field| **field == header)
+                    .map(|field| match *field {
+                        "images" => "images_urls",
+                        field => field,
+                    })
+            })
+        })
+        .collect();
+
+    if let Some(missing) = REQUIRED_FIELDS
+        .iter()
+        .find(|field| !columns.contains(&Some(**field)))
+    {
+        return Err(ApiError::InvalidInput(
+            "csv",
+            format!("Missing required column {}", missing),
+        ));
+    }
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for (index, record) in reader.records().enumerate() {
+        total_rows += 1;
+        // The header is line 1, so data starts on line 2.
+        let fallback_line = index + 2;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    row: e
+                        .position()
+                        .map_or(fallback_line, |position| position.line() as usize),
+                    field: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let row = record
+            .position()
+            .map_or(fallback_line, |position| position.line() as usize);
+
+        let values: HashMap<&'static str, &str> = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| column.map(|column| (column, restore_formula(value))))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+
+        match parse_row(&values) {
+            Ok(property) => rows.push(ImportRow {
+                row,
+                property: property.to_property(tenant_id),
+                images_urls: property.images_urls,
+            }),
+            Err(row_errors) => {
+                errors.extend(
+                    row_errors
+                        .into_iter()
+                        .map(|(field, message)| ImportRowError {
+                            row,
+                            field: Some(field),
+                            message,
+                        }),
+                )
+            }
+        }
+    }
+
+    Ok((rows, errors, total_rows))
+}
+
+/// Builds and validates a `CreateProperty`, collecting every `(field, message)` problem.
+fn parse_row(
+    values: &HashMap<&'static str, &str>,
+) -> Result<CreateProperty, Vec<(String, String)>> {
+    let mut errors = Vec::new();
+
+    let property = CreateProperty {
+        title: required(values, "title", &mut errors).unwrap_or_default(),
+        description: text(values, "description"),
+        property_type: required(values, "property_type", &mut errors).unwrap_or_default(),
+        status: required(values, "status", &mut errors).unwrap_or_default(),
+        price: required(values, "price", &mut errors).unwrap_or_default(),
+        currency: required(values, "currency", &mut errors).unwrap_or_default(),
+        bedrooms: optional(values, "bedrooms", &mut errors),
+        bathrooms: optional(values, "bathrooms", &mut errors),
+        parking_spaces: optional(values, "parking_spaces", &mut errors),
+        total_area: optional(values, "total_area", &mut errors),
+        built_area: optional(values, "built_area", &mut errors),
+        year_built: optional(values, "year_built", &mut errors),
+        address: text(values, "address"),
+        city: text(values, "city"),
+        state: text(values, "state"),
+        country: text(values, "country"),
+        google_maps_url: text(values, "google_maps_url"),
+        amenities: list(values, "amenities"),
+        images_urls: list(values, "images_urls").unwrap_or_default(),
+        slug: None,
+        meta_title: None,
+        meta_description: None,
+        og_image: None,
+    };
+
+    if let Some(url) = property.images_urls.iter().find(|url| !url.validate_url()) {
+        errors.push((
+            "images_urls".to_string(),
+            format!("{} is not a valid URL", url),
+        ));
+    }
+
+    if let Err(validation_errors) = property.validate() {
+        for (field, field_errors) in validation_errors.field_errors() {
+            for error in field_errors {
+                let message = error
+                    .message
+                    .as_ref()
+                    .map(|message| message.to_string())
+                    .unwrap_or_else(|| error.code.to_string());
+                errors.push((field.to_string(), message));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(property)
+    } else {
+        Err(errors)
+    }
+}
+
+fn text(values: &HashMap<&'static str, &str>, field: &str) -> Option<String> {
+    values.get(field).map(|value| value.to_string())
+}
+
+fn list(values: &HashMap<&'static str, &str>, field: &str) -> Option<Vec<String>> {
+    values.get(field).map(|value| {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+fn required<T: FromStr>(
+    values: &HashMap<&'static str, &str>,
+    field: &str,
+    errors: &mut Vec<(String, String)>,
+) -> Option<T> {
+    if !values.contains_key(field) {
+        errors.push((field.to_string(), "is required".to_string()));
+        return None;
+    }
+    optional(values, field, errors)
+}
+
+fn optional<T: FromStr>(
+    values: &HashMap<&'static str, &str>,
+    field: &str,
+    errors: &mut Vec<(String, String)>,
+) -> Option<T> {
+    let value = values.get(field)?;
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            errors.push((field.to_string(), format!("{} is not a valid value", value)));
+            None
+        }
+    }
+}
@@ -1,22 +1,45 @@
 use actix_web::web;
+use export::export_properties;
 use handler::{
     create_property, delete_property, generate_presigned_urls, get_property_by_id,
-    get_tenant_properties, update_property,
+    get_property_by_slug, get_property_seo, get_similar_properties, get_tenant_properties,
+    get_trash, patch_property, restore_property, update_property,
 };
+use import::{import_properties, MAX_IMPORT_BYTES};
 
+mod export;
 mod handler;
+mod import;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/properties")
             .route("", web::post().to(create_property))
+            .route("/trash", web::get().to(get_trash))
+            .route("/export", web::get().to(export_properties))
+            .service(
+                web::resource("/import")
+                    .app_data(web::PayloadConfig::new(MAX_IMPORT_BYTES))
+                    .route(web::post().to(import_properties)),
+            )
+            .route("/{property_id}/restore", web::post().to(restore_property))
+            .route("/{property_id}/seo", web::get().to(get_property_seo))
+            .route(
+                "/{property_id}/similar",
+                web::get().to(get_similar_properties),
+            )
             .route("/{property_id}", web::get().to(get_property_by_id))
             .route("/{property_id}", web::delete().to(delete_property))
-            .route("{property_id}", web::put().to(update_property)),
+            .route("{property_id}", web::put().to(update_property))
+            .route("/{property_id}", web::patch().to(patch_property)),
     )
     .service(
         web::scope("/tenants/{tenant_id}")
             .route("/properties", web::get().to(get_tenant_properties))
+            .route(
+                "/properties/by-slug/{slug}",
+                web::get().to(get_property_by_slug),
+            )
             .route(
                 "/generate_presigned_urls",
                 web::post().to(generate_presigned_urls),
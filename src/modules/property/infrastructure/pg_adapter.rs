@@ -1,153 +1,443 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use serde_json::Value as JsonValue;
 use sqlx::FromRow;
 use sqlx::Row;
+use sqlx::{Connection, PgConnection, Postgres, QueryBuilder};
 
 use crate::error::ApiError;
+use crate::modules::audit::{self, AuditAction, AuditEntity, NewAuditEntry};
+use crate::modules::plan;
 use crate::modules::property::port::DBRepository;
-use crate::modules::property::{Property, PropertyImage, PropertyWithImages};
-use crate::utils::database::{Filter, PaginatedRecord, Pagination, PostgresRepository, Value};
+use crate::modules::property::{
+    Property, PropertyImage, PropertyPatch, PropertySearchHit, PropertyWithImages,
+};
+use crate::utils::database::{
+    cursor_stream, push_set, Filter, PaginatedRecord, Pagination, PostgresRepository, Value,
+};
+use crate::utils::etag::Precondition;
 
-#[async_trait]
-impl DBRepository for PostgresRepository {
-    async fn create(
-        &self,
-        property: Property,
-        images: &[PropertyImage],
-    ) -> Result<PropertyWithImages, ApiError> {
-        let mut tx = self
-            .pg_pool
-            .begin()
+/// Locks the tenant's property for the rest of the transaction and returns it as it is.
+async fn lock_property(
+    conn: &mut PgConnection,
+    id: i32,
+    tenant_id: i32,
+) -> Result<PropertyWithImages, ApiError> {
+    let property = sqlx::query_as::<_, Property>(
+        "SELECT * FROM properties WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(ApiError::DatabaseError)?
+    .ok_or_else(|| ApiError::NotFound("Property not found or tenant mismatch".to_string()))?;
+    let images = find_images(conn, id).await?;
+
+    Ok(PropertyWithImages { property, images })
+}
+
+async fn find_images(
+    conn: &mut PgConnection,
+    property_id: i32,
+) -> Result<Vec<PropertyImage>, ApiError> {
+    sqlx::query_as::<_, PropertyImage>("SELECT * FROM property_images WHERE property_id = $1")
+        .bind(property_id)
+        .fetch_all(conn)
+        .await
+        .map_err(ApiError::DatabaseError)
+}
+
+/// Records the change in the audit log as part of the same transaction, so
+/// the entry exists exactly when the change does.
+async fn audit(
+    conn: &mut PgConnection,
+    actor_user_id: &str,
+    action: AuditAction,
+    before: Option<&PropertyWithImages>,
+    after: Option<&PropertyWithImages>,
+) -> Result<(), ApiError> {
+    let Some(subject) = after.or(before) else {
+        return Ok(());
+    };
+
+    let entry = NewAuditEntry::new(
+        actor_user_id,
+        subject.property.tenant_id,
+        AuditEntity::Property,
+        subject.property.id,
+        action,
+        before.map(PropertyWithImages::audited).as_ref(),
+        after.map(PropertyWithImages::audited).as_ref(),
+    );
+    audit::infrastructure::insert_entry(conn, &entry).await?;
+
+    Ok(())
+}
+
+/// Fails when `adding` more active listings would take the tenant past `limit`.
+/// The tenant stays locked until the transaction ends, so concurrent creates
+/// are counted one after the other.
+async fn reserve_listings(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+    adding: usize,
+    limit: Option<u32>,
+) -> Result<(), ApiError> {
+    if limit.is_none() {
+        return Ok(());
+    }
+
+    let usage = plan::infrastructure::lock_usage(conn, tenant_id).await?;
+    plan::check_limit(
+        "max_active_listings",
+        limit,
+        usage.active_listings as usize + adding,
+    )
+}
+
+/// First slug neither used nor redirected by another of the tenant's properties:
+/// `base`, then `base-2`, `base-3`...
+async fn available_slug(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+    base: &str,
+    property_id: Option<i32>,
+) -> Result<String, ApiError> {
+    let taken: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT slug FROM properties
+        WHERE tenant_id = $1 AND id IS DISTINCT FROM $3 AND (slug = $2 OR slug LIKE $2 || '-%')
+        UNION
+        SELECT slug FROM property_slug_redirects
+        WHERE tenant_id = $1 AND property_id IS DISTINCT FROM $3 AND (slug = $2 OR slug LIKE $2 || '-%')
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(base)
+    .bind(property_id)
+    .fetch_all(conn)
+    .await
+    .map_err(ApiError::DatabaseError)?;
+
+    Ok(std::iter::once(base.to_string())
+        .chain((2..).map(|n| format!("{}-{}", base, n)))
+        .find(|slug| !taken.contains(slug))
+        .unwrap_or_default())
+}
+
+#[derive(FromRow)]
+struct SlugSource {
+    slug: String,
+    title: String,
+    city: Option<String>,
+}
+
+/// Slug the property has to move to, if any: the requested one, or a new one
+/// generated when the title or city changed. `None` title or city means unchanged.
+async fn next_slug(
+    conn: &mut PgConnection,
+    tenant_id: i32,
+    id: i32,
+    requested: Option<String>,
+    title: Option<&str>,
+    city: Option<Option<&str>>,
+) -> Result<Option<String>, ApiError> {
+    let current =
+        sqlx::query_as::<_, SlugSource>("SELECT slug, title, city FROM properties WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *conn)
             .await
             .map_err(ApiError::DatabaseError)?;
+    let title = title.unwrap_or(&current.title);
+    let city = city.unwrap_or(current.city.as_deref());
+
+    let slug = match requested {
+        Some(requested) => requested,
+        None if current.title == title && current.city.as_deref() == city => return Ok(None),
+        None => {
+            let base = Property::generated_slug(title, city);
+            available_slug(&mut *conn, tenant_id, &base, Some(id)).await?
+        }
+    };
+    if slug == current.slug {
+        return Ok(None);
+    }
+
+    // The old slug keeps pointing at the property, and the new one stops redirecting elsewhere
+    sqlx::query(
+        r#"
+        INSERT INTO property_slug_redirects (tenant_id, slug, property_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (tenant_id, slug) DO UPDATE
+        SET property_id = EXCLUDED.property_id, created_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(&current.slug)
+    .bind(id)
+    .execute(&mut *conn)
+    .await
+    .map_err(ApiError::DatabaseError)?;
+
+    sqlx::query("DELETE FROM property_slug_redirects WHERE tenant_id = $1 AND slug = $2")
+        .bind(tenant_id)
+        .bind(&slug)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::DatabaseError)?;
 
-        // Insert property
-        let inserted_property = sqlx::query_as::<_, Property>(
+    Ok(Some(slug))
+}
+
+/// Times an insert picks another slug after losing it to a concurrent one.
+const MAX_SLUG_ATTEMPTS: u32 = 3;
+
+/// Maps errors of writes that may set a slug already used by another property.
+fn slug_conflict(err: sqlx::Error, not_found: impl FnOnce() -> ApiError) -> ApiError {
+    match err {
+        sqlx::Error::RowNotFound => not_found(),
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ApiError::Conflict("Slug is already used by another property".to_string())
+        }
+        _ => ApiError::DatabaseError(err),
+    }
+}
+
+/// Inserts a property and its images on the given connection.
+async fn insert_property(
+    conn: &mut PgConnection,
+    property: &Property,
+    images: &[PropertyImage],
+) -> Result<PropertyWithImages, ApiError> {
+    let base_slug = match &property.slug {
+        Some(slug) => slug.clone(),
+        None => Property::generated_slug(&property.title, property.city.as_deref()),
+    };
+    let mut attempts = 0;
+    let inserted_property = loop {
+        let slug = available_slug(&mut *conn, property.tenant_id, &base_slug, None).await?;
+
+        // A concurrent create may commit the same slug first. The insert then
+        // runs again with the next free one, under a savepoint so the
+        // transaction survives the unique violation.
+        let mut savepoint = conn.begin().await.map_err(ApiError::DatabaseError)?;
+        match insert_property_row(&mut savepoint, property, &slug).await {
+            Ok(inserted_property) => {
+                savepoint.commit().await.map_err(ApiError::DatabaseError)?;
+                break inserted_property;
+            }
+            Err(sqlx::Error::Database(e))
+                if e.is_unique_violation() && attempts < MAX_SLUG_ATTEMPTS =>
+            {
+                savepoint
+                    .rollback()
+                    .await
+                    .map_err(ApiError::DatabaseError)?;
+                attempts += 1;
+            }
+            Err(err) => {
+                return Err(slug_conflict(err, || {
+                    ApiError::UnexpectedError("Inserted property was not returned".to_string())
+                }))
+            }
+        }
+    };
+
+    let inserted_images = insert_images(&mut *conn, inserted_property.id, images).await?;
+
+    Ok(PropertyWithImages {
+        property: inserted_property,
+        images: inserted_images,
+    })
+}
+
+/// Inserts the property row alone, leaving errors unmapped so a lost slug can be retried.
+async fn insert_property_row(
+    conn: &mut PgConnection,
+    property: &Property,
+    slug: &str,
+) -> Result<Property, sqlx::Error> {
+    sqlx::query_as::<_, Property>(
+        r#"
+        INSERT INTO properties (
+            tenant_id, title, description, property_type, status, price, currency,
+            bedrooms, bathrooms, parking_spaces, total_area, built_area, year_built,
+            address, city, state, country, google_maps_url, amenities,
+            slug, meta_title, meta_description, og_image
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19,
+                $20, $21, $22, $23)
+        RETURNING *
+        "#,
+    )
+    .bind(&property.tenant_id)
+    .bind(&property.title)
+    .bind(&property.description)
+    .bind(&property.property_type)
+    .bind(&property.status)
+    .bind(&property.price)
+    .bind(&property.currency)
+    .bind(&property.bedrooms)
+    .bind(&property.bathrooms)
+    .bind(&property.parking_spaces)
+    .bind(&property.total_area)
+    .bind(&property.built_area)
+    .bind(&property.year_built)
+    .bind(&property.address)
+    .bind(&property.city)
+    .bind(&property.state)
+    .bind(&property.country)
+    .bind(&property.google_maps_url)
+    .bind(&property.amenities)
+    .bind(slug)
+    .bind(&property.meta_title)
+    .bind(&property.meta_description)
+    .bind(&property.og_image)
+    .fetch_one(conn)
+    .await
+}
+
+/// Inserts images of the property on the given connection.
+async fn insert_images(
+    conn: &mut PgConnection,
+    property_id: i32,
+    images: &[PropertyImage],
+) -> Result<Vec<PropertyImage>, ApiError> {
+    let mut inserted_images = Vec::new();
+    for image in images {
+        let inserted_image = sqlx::query_as::<_, PropertyImage>(
             r#"
-            INSERT INTO properties (
-                tenant_id, title, description, property_type, status, price, currency,
-                bedrooms, bathrooms, parking_spaces, total_area, built_area, year_built,
-                address, city, state, country, google_maps_url, amenities
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            INSERT INTO property_images (property_id, image_url, is_primary)
+            VALUES ($1, $2, $3)
             RETURNING *
             "#,
         )
-        .bind(&property.tenant_id)
-        .bind(&property.title)
-        .bind(&property.description)
-        .bind(&property.property_type)
-        .bind(&property.status)
-        .bind(&property.price)
-        .bind(&property.currency)
-        .bind(&property.bedrooms)
-        .bind(&property.bathrooms)
-        .bind(&property.parking_spaces)
-        .bind(&property.total_area)
-        .bind(&property.built_area)
-        .bind(&property.year_built)
-        .bind(&property.address)
-        .bind(&property.city)
-        .bind(&property.state)
-        .bind(&property.country)
-        .bind(&property.google_maps_url)
-        .bind(&property.amenities)
-        .fetch_one(&mut *tx)
+        .bind(property_id)
+        .bind(&image.image_url)
+        .bind(&image.is_primary)
+        .fetch_one(&mut *conn)
         .await
         .map_err(ApiError::DatabaseError)?;
 
-        // Insert images
-        let mut inserted_images = Vec::new();
-        for image in images {
-            let inserted_image = sqlx::query_as::<_, PropertyImage>(
-                r#"
-                INSERT INTO property_images (property_id, image_url, is_primary)
-                VALUES ($1, $2, $3)
-                RETURNING *
-                "#,
-            )
-            .bind(&inserted_property.id)
-            .bind(&image.image_url)
-            .bind(&image.is_primary)
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(ApiError::DatabaseError)?;
+        inserted_images.push(inserted_image);
+    }
 
-            inserted_images.push(inserted_image);
-        }
+    Ok(inserted_images)
+}
 
-        tx.commit().await.map_err(ApiError::DatabaseError)?;
+/// Replaces the images of the property, or returns the current ones when
+/// `images` is `None`.
+async fn replace_images(
+    conn: &mut PgConnection,
+    property_id: i32,
+    images: Option<&[PropertyImage]>,
+) -> Result<Vec<PropertyImage>, ApiError> {
+    let Some(images) = images else {
+        return find_images(conn, property_id).await;
+    };
 
-        Ok(PropertyWithImages {
-            property: inserted_property,
-            images: inserted_images,
-        })
-    }
+    sqlx::query("DELETE FROM property_images WHERE property_id = $1")
+        .bind(property_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+    insert_images(conn, property_id, images).await
+}
 
-    async fn edit_property_images(
+#[async_trait]
+impl DBRepository for PostgresRepository {
+    async fn create(
         &self,
-        property_id: i32,
+        property: Property,
         images: &[PropertyImage],
-    ) -> Result<Vec<PropertyImage>, ApiError> {
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
         let mut tx = self
             .pg_pool
             .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-        // Check if the property exists
-        let property_exists =
-            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM properties WHERE id = $1)")
-                .bind(property_id)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(ApiError::DatabaseError)?;
+        reserve_listings(&mut tx, property.tenant_id, 1, max_active_listings).await?;
+        let inserted = insert_property(&mut tx, &property, images).await?;
+        audit(
+            &mut tx,
+            actor_user_id,
+            AuditAction::Create,
+            None,
+            Some(&inserted),
+        )
+        .await?;
 
-        if !property_exists {
-            return Err(ApiError::NotFound(format!(
-                "Property with id {} not found",
-                property_id
-            )));
-        }
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        // Delete existing images
-        sqlx::query("DELETE FROM property_images WHERE property_id = $1")
-            .bind(&property_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(ApiError::DatabaseError)?;
+        Ok(inserted)
+    }
 
-        // Insert new images
-        let mut inserted_images = Vec::new();
-        for image in images {
-            let inserted_image = sqlx::query_as::<_, PropertyImage>(
-                r#"
-                INSERT INTO property_images (property_id, image_url, is_primary)
-                VALUES ($1, $2, $3)
-                RETURNING *
-                "#,
-            )
-            .bind(&property_id)
-            .bind(&image.image_url)
-            .bind(&image.is_primary)
-            .fetch_one(&mut *tx)
+    async fn create_many(
+        &self,
+        tenant_id: i32,
+        properties: &[(Property, Vec<PropertyImage>)],
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
+    ) -> Result<Vec<PropertyWithImages>, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-            inserted_images.push(inserted_image);
+        reserve_listings(&mut tx, tenant_id, properties.len(), max_active_listings).await?;
+
+        let mut inserted = Vec::with_capacity(properties.len());
+        for (property, images) in properties {
+            let created = insert_property(&mut tx, property, images).await?;
+            audit(
+                &mut tx,
+                actor_user_id,
+                AuditAction::Create,
+                None,
+                Some(&created),
+            )
+            .await?;
+            inserted.push(created);
         }
 
         tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        Ok(inserted_images)
+        Ok(inserted)
     }
 
-    async fn update_property(&self, property: Property) -> Result<PropertyWithImages, ApiError> {
+    async fn update_property(
+        &self,
+        property: Property,
+        images: &[PropertyImage],
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
         let mut tx = self
             .pg_pool
             .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
+        let before = lock_property(&mut tx, property.id, property.tenant_id).await?;
+        precondition.ensure(before.property.updated_at)?;
+
+        let slug = next_slug(
+            &mut tx,
+            property.tenant_id,
+            property.id,
+            property.slug.clone(),
+            Some(&property.title),
+            Some(property.city.as_deref()),
+        )
+        .await?;
+
         // Update property
         let updated_property = sqlx::query_as::<_, Property>(
             r#"
@@ -156,8 +446,9 @@ impl DBRepository for PostgresRepository {
                 status = $5, price = $6, currency = $7, bedrooms = $8, bathrooms = $9,
                 parking_spaces = $10, total_area = $11, built_area = $12, year_built = $13,
                 address = $14, city = $15, state = $16, country = $17, google_maps_url = $18,
-                amenities = $19, updated_at = CURRENT_TIMESTAMP
-            WHERE id = $20
+                amenities = $19, slug = COALESCE($21, slug), meta_title = $22,
+                meta_description = $23, og_image = $24, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $20 AND tenant_id = $1
             RETURNING *
             "#,
         )
@@ -181,42 +472,138 @@ impl DBRepository for PostgresRepository {
         .bind(&property.google_maps_url)
         .bind(&property.amenities)
         .bind(&property.id)
+        .bind(&slug)
+        .bind(&property.meta_title)
+        .bind(&property.meta_description)
+        .bind(&property.og_image)
         .fetch_one(&mut *tx)
         .await
-        .map_err(|err| match err {
-            sqlx::Error::RowNotFound => {
+        .map_err(|err| {
+            slug_conflict(err, || {
                 ApiError::NotFound(format!("Property with id {} not found", property.id))
-            }
-            _ => ApiError::DatabaseError(err),
+            })
         })?;
 
-        // Fetch images
-        let images = sqlx::query_as::<_, PropertyImage>(
-            "SELECT * FROM property_images WHERE property_id = $1",
+        // Images are replaced under the same lock, so they pass the same version check
+        let images = replace_images(&mut tx, property.id, Some(images)).await?;
+        let updated = PropertyWithImages {
+            property: updated_property,
+            images,
+        };
+        audit(
+            &mut tx,
+            actor_user_id,
+            AuditAction::Update,
+            Some(&before),
+            Some(&updated),
         )
-        .bind(&property.id)
-        .fetch_all(&mut *tx)
-        .await
-        .map_err(ApiError::DatabaseError)?;
+        .await?;
 
         tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        Ok(PropertyWithImages {
-            property: updated_property,
+        Ok(updated)
+    }
+
+    async fn patch_property(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        patch: PropertyPatch,
+        images: Option<&[PropertyImage]>,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let before = lock_property(&mut tx, id, tenant_id).await?;
+        precondition.ensure(before.property.updated_at)?;
+
+        let slug = next_slug(
+            &mut tx,
+            tenant_id,
+            id,
+            patch.slug,
+            patch.title.as_deref(),
+            patch.city.as_ref().map(Option::as_deref),
+        )
+        .await?;
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE properties SET ");
+        let mut set = query.separated(", ");
+        push_set(&mut set, "title", patch.title);
+        push_set(&mut set, "description", patch.description);
+        push_set(&mut set, "property_type", patch.property_type);
+        push_set(&mut set, "status", patch.status);
+        push_set(&mut set, "price", patch.price);
+        push_set(&mut set, "currency", patch.currency);
+        push_set(&mut set, "bedrooms", patch.bedrooms);
+        push_set(&mut set, "bathrooms", patch.bathrooms);
+        push_set(&mut set, "parking_spaces", patch.parking_spaces);
+        push_set(&mut set, "total_area", patch.total_area);
+        push_set(&mut set, "built_area", patch.built_area);
+        push_set(&mut set, "year_built", patch.year_built);
+        push_set(&mut set, "address", patch.address);
+        push_set(&mut set, "city", patch.city);
+        push_set(&mut set, "state", patch.state);
+        push_set(&mut set, "country", patch.country);
+        push_set(&mut set, "google_maps_url", patch.google_maps_url);
+        push_set(&mut set, "amenities", patch.amenities);
+        push_set(&mut set, "slug", slug);
+        push_set(&mut set, "meta_title", patch.meta_title);
+        push_set(&mut set, "meta_description", patch.meta_description);
+        push_set(&mut set, "og_image", patch.og_image);
+        set.push("updated_at = CURRENT_TIMESTAMP");
+
+        query
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(" AND tenant_id = ")
+            .push_bind(tenant_id)
+            .push(" RETURNING *");
+
+        let patched_property = query
+            .build_query_as::<Property>()
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|err| {
+                slug_conflict(err, || {
+                    ApiError::NotFound("Property not found or tenant mismatch".to_string())
+                })
+            })?;
+
+        // Images are part of the patch, so they pass the same version check
+        let images = replace_images(&mut tx, id, images).await?;
+        let patched = PropertyWithImages {
+            property: patched_property,
             images,
-        })
+        };
+        audit(
+            &mut tx,
+            actor_user_id,
+            AuditAction::Update,
+            Some(&before),
+            Some(&patched),
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(patched)
     }
 
     async fn find(&self, filter: Filter) -> Result<PropertyWithImages, ApiError> {
         let (where_clause, args) = filter.build_for_sqlx();
 
+        // The property is picked first so every one of its images is joined
         let query = format!(
             "SELECT p.*, pi.id as image_id, pi.image_url, pi.is_primary
-         FROM properties p
-         LEFT JOIN property_images pi ON p.id = pi.property_id
-         WHERE {}
-         LIMIT 1",
-            where_clause.replace("id", "p.id")
+         FROM (SELECT * FROM properties WHERE {} LIMIT 1) p
+         LEFT JOIN property_images pi ON p.id = pi.property_id",
+            where_clause
         );
 
         let mut query_builder = sqlx::query(&query);
@@ -256,6 +643,21 @@ impl DBRepository for PostgresRepository {
         Ok(PropertyWithImages { property, images })
     }
 
+    async fn find_slug_redirect(
+        &self,
+        tenant_id: i32,
+        slug: &str,
+    ) -> Result<Option<i32>, ApiError> {
+        sqlx::query_scalar(
+            "SELECT property_id FROM property_slug_redirects WHERE tenant_id = $1 AND slug = $2",
+        )
+        .bind(tenant_id)
+        .bind(slug)
+        .fetch_optional(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)
+    }
+
     async fn find_many(
         &self,
         filter: Filter,
@@ -367,63 +769,302 @@ impl DBRepository for PostgresRepository {
         ))
     }
 
-    async fn delete(&self, id: i32, tenant_id: i32) -> Result<PropertyWithImages, ApiError> {
+    async fn search(
+        &self,
+        filter: Filter,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<PropertySearchHit>, ApiError> {
+        let (where_clause, mut args) = filter.build_for_sqlx();
+        let query_arg = args.len() + 1;
+        args.push(Value::String(query.to_string()));
+
+        let count_query = format!(
+            "SELECT COUNT(*) FROM properties
+             WHERE {} AND search_vector @@ websearch_to_tsquery('spanish', ${})",
+            where_clause, query_arg
+        );
+        let mut count_query_builder = sqlx::query_scalar::<_, i64>(&count_query);
+        for arg in args.clone() {
+            count_query_builder = match arg {
+                Value::Int(i) => count_query_builder.bind(i),
+                Value::Float(f) => count_query_builder.bind(f),
+                Value::String(s) => count_query_builder.bind(s),
+                Value::Bool(b) => count_query_builder.bind(b),
+                Value::Json(j) => count_query_builder.bind(j),
+            };
+        }
+        let total_items = count_query_builder
+            .fetch_one(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let offset = (pagination.page - 1) * pagination.per_page;
+
+        // Snippets are only built for the page being returned. The text is escaped
+        // first so the only markup in them is the `<mark>` around matches.
+        let query = format!(
+            "WITH hits AS (
+                SELECT p.*, ts_rank_cd(p.search_vector, q) AS rank
+                FROM properties p, websearch_to_tsquery('spanish', ${query_arg}) q
+                WHERE {where_clause} AND p.search_vector @@ q
+                ORDER BY rank DESC, p.id
+                LIMIT {limit} OFFSET {offset}
+            )
+            SELECT hits.*,
+                ts_headline(
+                    'spanish',
+                    replace(replace(replace(
+                        concat_ws(' — ', hits.title, hits.description),
+                        '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                    websearch_to_tsquery('spanish', ${query_arg}),
+                    'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2'
+                ) AS snippet,
+                (
+                    SELECT COALESCE(
+                        json_agg(
+                            json_build_object(
+                                'id', pi.id,
+                                'property_id', pi.property_id,
+                                'image_url', pi.image_url,
+                                'is_primary', pi.is_primary
+                            )
+                            ORDER BY pi.id
+                        ),
+                        '[]'
+                    )
+                    FROM property_images pi
+                    WHERE pi.property_id = hits.id
+                ) AS images
+            FROM hits
+            ORDER BY hits.rank DESC, hits.id",
+            limit = pagination.per_page,
+        );
+
+        let mut query_builder = sqlx::query(&query);
+        for arg in args {
+            query_builder = match arg {
+                Value::Int(i) => query_builder.bind(i),
+                Value::Float(f) => query_builder.bind(f),
+                Value::String(s) => query_builder.bind(s),
+                Value::Bool(b) => query_builder.bind(b),
+                Value::Json(j) => query_builder.bind(j),
+            };
+        }
+
+        let hits = query_builder
+            .fetch_all(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?
+            .iter()
+            .map(|row| {
+                let property = Property::from_row(row).map_err(ApiError::DatabaseError)?;
+                let images: Vec<PropertyImage> =
+                    serde_json::from_value(row.get::<JsonValue, _>("images"))?;
+                Ok(PropertySearchHit {
+                    listing: PropertyWithImages { property, images },
+                    rank: row.get("rank"),
+                    snippet: row.get("snippet"),
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        Ok(PaginatedRecord::new(
+            hits,
+            total_items as u64,
+            pagination.page,
+            pagination.per_page,
+        ))
+    }
+
+    fn stream(&self, filter: Filter) -> BoxStream<'static, Result<PropertyWithImages, ApiError>> {
+        let (where_clause, args) = filter.build_for_sqlx();
+
+        // Images are aggregated so each cursor row is a complete listing.
+        let query = format!(
+            "SELECT p.*,
+                COALESCE(
+                    json_agg(
+                        json_build_object(
+                            'id', pi.id,
+                            'property_id', pi.property_id,
+                            'image_url', pi.image_url,
+                            'is_primary', pi.is_primary
+                        )
+                        ORDER BY pi.id
+                    ) FILTER (WHERE pi.id IS NOT NULL),
+                    '[]'
+                ) AS images
+             FROM properties p
+             LEFT JOIN property_images pi ON p.id = pi.property_id
+             WHERE {}
+             GROUP BY p.id
+             ORDER BY p.id",
+            where_clause
+        );
+
+        cursor_stream(self.pg_pool.clone(), query, args, |row| {
+            let property = Property::from_row(row).map_err(ApiError::DatabaseError)?;
+            let images: Vec<PropertyImage> =
+                serde_json::from_value(row.get::<JsonValue, _>("images"))?;
+            Ok(PropertyWithImages { property, images })
+        })
+    }
+
+    async fn delete(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
         let mut tx = self
             .pg_pool
             .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-        // Fetch the property to return it after deletion
-        let property_row = sqlx::query(
+        let before = lock_property(&mut tx, id, tenant_id).await?;
+        precondition.ensure(before.property.updated_at)?;
+
+        // Move the property to the trash; the purge job removes it for good
+        let property = sqlx::query_as::<_, Property>(
             r#"
-            SELECT p.*, pi.id as image_id, pi.image_url, pi.is_primary
-            FROM properties p
-            LEFT JOIN property_images pi ON p.id = pi.property_id
-            WHERE p.id = $1 AND p.tenant_id = $2
+            UPDATE properties
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND tenant_id = $2
+            RETURNING *
             "#,
         )
         .bind(id)
         .bind(tenant_id)
-        .fetch_all(&mut *tx)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(ApiError::DatabaseError)?;
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::NotFound("Property not found or tenant mismatch".to_string())
+            }
+            _ => ApiError::DatabaseError(err),
+        })?;
+        audit(
+            &mut tx,
+            actor_user_id,
+            AuditAction::Delete,
+            Some(&before),
+            None,
+        )
+        .await?;
 
-        if property_row.is_empty() {
-            return Err(ApiError::NotFound(
-                "Property not found or tenant mismatch".to_string(),
-            ));
-        }
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        let property =
-            Property::from_row(&property_row[0]).map_err(|e| ApiError::DatabaseError(e.into()))?;
+        Ok(PropertyWithImages {
+            property,
+            images: before.images,
+        })
+    }
 
-        let images: Vec<PropertyImage> = property_row
-            .into_iter()
-            .filter_map(|row| {
-                let image_id: Option<i32> = row.get("image_id");
-                image_id
-                    .map(|_| PropertyImage::from_row(&row).ok())
-                    .flatten()
-            })
-            .collect();
+    async fn restore(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
 
-        // Delete property
-        let result = sqlx::query("DELETE FROM properties WHERE id = $1 AND tenant_id = $2")
-            .bind(id)
-            .bind(tenant_id)
-            .execute(&mut *tx)
+        reserve_listings(&mut tx, tenant_id, 1, max_active_listings).await?;
+
+        let property = sqlx::query_as::<_, Property>(
+            r#"
+            UPDATE properties
+            SET deleted_at = NULL
+            WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NOT NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::NotFound(format!("Property with id {} not found in trash", id))
+            }
+            _ => ApiError::DatabaseError(err),
+        })?;
+        let images = find_images(&mut tx, id).await?;
+        let restored = PropertyWithImages { property, images };
+        audit(
+            &mut tx,
+            actor_user_id,
+            AuditAction::Restore,
+            None,
+            Some(&restored),
+        )
+        .await?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(restored)
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<PropertyWithImages>, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
             .await
             .map_err(ApiError::DatabaseError)?;
 
-        if result.rows_affected() == 0 {
-            return Err(ApiError::NotFound(
-                "Property not found or tenant mismatch".to_string(),
-            ));
+        // Lock the expired rows first so a concurrent restore cannot lose its images
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT id FROM properties WHERE deleted_at < $1 FOR UPDATE",
+        )
+        .bind(deleted_before)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
         }
 
+        let mut images = sqlx::query_as::<_, PropertyImage>(
+            "SELECT * FROM property_images WHERE property_id = ANY($1)",
+        )
+        .bind(&ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        // Images are removed by the ON DELETE CASCADE on property_images
+        let properties =
+            sqlx::query_as::<_, Property>("DELETE FROM properties WHERE id = ANY($1) RETURNING *")
+                .bind(&ids)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(ApiError::DatabaseError)?;
+
         tx.commit().await.map_err(ApiError::DatabaseError)?;
 
-        Ok(PropertyWithImages { property, images })
+        Ok(properties
+            .into_iter()
+            .map(|property| {
+                let (property_images, rest): (Vec<_>, Vec<_>) = images
+                    .drain(..)
+                    .partition(|image| image.property_id == property.id);
+                images = rest;
+                PropertyWithImages {
+                    property,
+                    images: property_images,
+                }
+            })
+            .collect())
     }
 }
@@ -1,32 +1,136 @@
-use std::time::Duration;
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use futures::future::join_all;
+use reqwest::{header::CONTENT_TYPE, redirect::Policy};
+use tokio::net::lookup_host;
+use url::Url;
 
 use crate::{error::ApiError, modules::property::port::BucketRepository, utils::s3::S3Repository};
 
+/// Largest image accepted when copying listings from another site.
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+const FETCH_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Whole download, so a slow server cannot hold an import open.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[async_trait]
 impl BucketRepository for S3Repository {
     async fn post_presigned_url(&self, key: &str) -> Result<String, ApiError> {
         self.post_presigned_url(key, Duration::from_secs(120)).await
     }
     async fn delete_images(&self, images: &[String]) -> Result<Vec<String>, ApiError> {
-        let delete_futures = images.iter().map(|image_url| {
-            let client = self.clone();
-            let image_url = image_url.clone();
-            async move { client.delete_object(&image_url).await.map(|_| image_url) }
-        });
-
-        let results = join_all(delete_futures).await;
-
-        let mut deleted_images = Vec::new();
-        for result in results {
-            match result {
-                Ok(image_url) => deleted_images.push(image_url),
-                Err(e) => return Err(e),
+        self.delete_objects(images).await
+    }
+
+    async fn upload_from_url(&self, url: &str, key: &str) -> Result<String, ApiError> {
+        let client = fetch_client(url).await?;
+        let mut response = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ApiError::BadRequest(format!("Could not fetch {}: {}", url, e)))?;
+        if response.status().is_redirection() {
+            return Err(ApiError::BadRequest(format!(
+                "{} redirects elsewhere, link the image itself",
+                url
+            )));
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !content_type.starts_with("image/") {
+            return Err(ApiError::BadRequest(format!("{} is not an image", url)));
+        }
+        if response.content_length().unwrap_or(0) > MAX_IMAGE_BYTES {
+            return Err(ApiError::BadRequest(format!("{} is too large", url)));
+        }
+
+        // Content-Length may be missing or lie, so the limit holds while reading.
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ApiError::BadRequest(format!("Could not fetch {}: {}", url, e)))?
+        {
+            if (body.len() + chunk.len()) as u64 > MAX_IMAGE_BYTES {
+                return Err(ApiError::BadRequest(format!("{} is too large", url)));
             }
+            body.extend_from_slice(&chunk);
         }
 
-        Ok(deleted_images)
+        self.put_object(key, body, &content_type).await
+    }
+}
+
+/// Client fetching `url` from the address its host resolves to now, so the
+/// host cannot be pointed at an internal address between the check and the
+/// request. Redirects are not followed, as they could lead anywhere.
+async fn fetch_client(url: &str) -> Result<reqwest::Client, ApiError> {
+    let invalid = |reason: &str| ApiError::BadRequest(format!("{} {}", url, reason));
+
+    let parsed = Url::parse(url).map_err(|_| invalid("is not a valid URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid("is not an http(s) URL"));
+    }
+    let host = parsed.host_str().ok_or_else(|| invalid("has no host"))?;
+    let port = parsed
+        .port_or_known_default()
+        .ok_or_else(|| invalid("has no port"))?;
+
+    let addresses: Vec<SocketAddr> = lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|_| invalid("could not be resolved"))?
+        .collect();
+    if addresses.is_empty() || !addresses.iter().all(|address| is_public(address.ip())) {
+        return Err(invalid("does not point to a public address"));
+    }
+
+    reqwest::Client::builder()
+        .connect_timeout(FETCH_CONNECT_TIMEOUT)
+        .timeout(FETCH_TIMEOUT)
+        .redirect(Policy::none())
+        .resolve_to_addrs(host, &addresses)
+        .build()
+        .map_err(|e| ApiError::UnexpectedError(e.to_string()))
+}
+
+/// Whether `ip` is routable on the internet, leaving out loopback, private,
+/// link-local (cloud metadata), shared, documentation and reserved ranges.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || first == 0x2001 && ip.segments()[1] == 0x0db8)
+        }
     }
 }
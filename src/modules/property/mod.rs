@@ -8,5 +8,7 @@ pub use model::*;
 
 pub mod infrastructure;
 
+mod similarity;
+
 mod service;
 pub use service::*;
@@ -1,6 +1,9 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::utils::slug::slugify;
+
 #[derive(Debug, Clone, Deserialize, Serialize, FromRow)]
 pub struct Property {
     pub id: i32,
@@ -23,6 +26,13 @@ pub struct Property {
     pub country: Option<String>,
     pub google_maps_url: Option<String>,
     pub amenities: Option<Vec<String>>,
+    /// Unique per tenant. Generated from the title on creation when not given.
+    pub slug: Option<String>,
+    pub meta_title: Option<String>,
+    pub meta_description: Option<String>,
+    pub og_image: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl Property {
@@ -72,10 +82,52 @@ impl Property {
             country: country.map(|s| s.as_ref().to_string()),
             google_maps_url: google_maps_url.map(|s| s.as_ref().to_string()),
             amenities,
+            slug: None,
+            meta_title: None,
+            meta_description: None,
+            og_image: None,
+            updated_at: None,
+            deleted_at: None,
+        }
+    }
+
+    /// Slug derived from the title and city, before it is made unique for the tenant.
+    pub fn generated_slug(title: &str, city: Option<&str>) -> String {
+        match city {
+            Some(city) => slugify(&format!("{} {}", title, city)),
+            None => slugify(title),
         }
     }
 }
 
+/// Columns to change on a property. `None` leaves a column untouched and
+/// `Some(None)` clears a nullable one.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyPatch {
+    pub title: Option<String>,
+    pub description: Option<Option<String>>,
+    pub property_type: Option<String>,
+    pub status: Option<String>,
+    pub price: Option<f64>,
+    pub currency: Option<String>,
+    pub bedrooms: Option<Option<i32>>,
+    pub bathrooms: Option<Option<i32>>,
+    pub parking_spaces: Option<Option<i32>>,
+    pub total_area: Option<Option<f64>>,
+    pub built_area: Option<Option<f64>>,
+    pub year_built: Option<Option<i32>>,
+    pub address: Option<Option<String>>,
+    pub city: Option<Option<String>>,
+    pub state: Option<Option<String>>,
+    pub country: Option<Option<String>>,
+    pub google_maps_url: Option<Option<String>>,
+    pub amenities: Option<Option<Vec<String>>>,
+    pub slug: Option<String>,
+    pub meta_title: Option<Option<String>>,
+    pub meta_description: Option<Option<String>>,
+    pub og_image: Option<Option<String>>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, FromRow)]
 pub struct PropertyImage {
     pub id: i32,
@@ -100,3 +152,139 @@ pub struct PropertyWithImages {
     pub property: Property,
     pub images: Vec<PropertyImage>,
 }
+
+/// What the audit log keeps of a listing: its fields and the URLs of its images.
+#[derive(Debug, Serialize)]
+pub struct AuditedProperty<'a> {
+    #[serde(flatten)]
+    pub property: &'a Property,
+    pub images: Vec<&'a str>,
+}
+
+const META_TITLE_LENGTH: usize = 60;
+const META_DESCRIPTION_LENGTH: usize = 160;
+
+impl PropertyWithImages {
+    pub fn audited(&self) -> AuditedProperty<'_> {
+        AuditedProperty {
+            property: &self.property,
+            images: self
+                .images
+                .iter()
+                .map(|image| image.image_url.as_str())
+                .collect(),
+        }
+    }
+
+    /// Metadata for the listing page: the agent's overrides, falling back to
+    /// values derived from the listing itself.
+    pub fn seo(&self) -> PropertySeo {
+        let property = &self.property;
+        let primary_image = self
+            .images
+            .iter()
+            .find(|image| image.is_primary)
+            .or(self.images.first());
+
+        PropertySeo {
+            property_id: property.id,
+            slug: property.slug.clone().unwrap_or_default(),
+            meta_title: property
+                .meta_title
+                .clone()
+                .unwrap_or_else(|| truncate(&property.title, META_TITLE_LENGTH)),
+            meta_description: property.meta_description.clone().or_else(|| {
+                property
+                    .description
+                    .as_deref()
+                    .map(|description| truncate(description, META_DESCRIPTION_LENGTH))
+            }),
+            og_image: property
+                .og_image
+                .clone()
+                .or_else(|| primary_image.map(|image| image.image_url.clone())),
+            updated_at: property.updated_at,
+        }
+    }
+}
+
+/// Cuts `text` to at most `max` characters at a word boundary, marking the cut with an ellipsis.
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max {
+        return text;
+    }
+
+    let cut: String = text.chars().take(max - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) => &cut[..space],
+        None => &cut,
+    };
+    format!("{}…", cut.trim_end_matches([',', '.', ';', ':']))
+}
+
+/// Listing matched by a full-text search.
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertySearchHit {
+    #[serde(flatten)]
+    pub listing: PropertyWithImages,
+    pub rank: f32,
+    /// Excerpt of the title and description, HTML-escaped, with matches wrapped in `<mark>`.
+    pub snippet: String,
+}
+
+/// Outcome of looking a listing up by slug.
+#[derive(Debug, Clone)]
+pub enum SlugLookup {
+    Found(Box<PropertyWithImages>),
+    /// The slug is a former one; the listing now lives at `SlugRedirect::slug`.
+    Moved(SlugRedirect),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlugRedirect {
+    pub property_id: i32,
+    pub slug: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertySeo {
+    pub property_id: i32,
+    pub slug: String,
+    pub meta_title: String,
+    pub meta_description: Option<String>,
+    pub og_image: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A spreadsheet row that passed validation and is ready to be imported.
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    /// Line of the uploaded file, counting the header as line 1.
+    pub row: usize,
+    pub property: Property,
+    pub images_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowError {
+    /// Line of the uploaded file, counting the header as line 1.
+    pub row: usize,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedRow {
+    pub row: usize,
+    pub property_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub imported: Vec<ImportedRow>,
+    pub errors: Vec<ImportRowError>,
+}
@@ -1,41 +1,111 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 
 use crate::{
     error::ApiError,
-    utils::database::{Filter, PaginatedRecord, Pagination},
+    utils::{
+        database::{Filter, PaginatedRecord, Pagination},
+        etag::Precondition,
+    },
 };
 
-use super::{Property, PropertyImage, PropertyWithImages};
+use super::{Property, PropertyImage, PropertyPatch, PropertySearchHit, PropertyWithImages};
 
+/// Writes taking an `actor_user_id` record themselves in the audit log, in
+/// the same transaction as the change.
 #[async_trait]
 pub trait DBRepository: Send + Sync {
+    /// Inserts the property unless the tenant already has `max_active_listings`.
     async fn create(
         &self,
         property: Property,
         images: &[PropertyImage],
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
     ) -> Result<PropertyWithImages, ApiError>;
 
-    async fn edit_property_images(
+    /// Inserts every property of the tenant in a single transaction; nothing is
+    /// stored if one fails or they would exceed `max_active_listings`.
+    async fn create_many(
         &self,
-        property_id: i32,
+        tenant_id: i32,
+        properties: &[(Property, Vec<PropertyImage>)],
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
+    ) -> Result<Vec<PropertyWithImages>, ApiError>;
+
+    /// Replaces the property and its images.
+    async fn update_property(
+        &self,
+        property: Property,
         images: &[PropertyImage],
-    ) -> Result<Vec<PropertyImage>, ApiError>;
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError>;
 
-    async fn update_property(&self, property: Property) -> Result<PropertyWithImages, ApiError>;
+    /// Applies the patch, replacing the images when `images` is given.
+    async fn patch_property(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        patch: PropertyPatch,
+        images: Option<&[PropertyImage]>,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError>;
 
     async fn find(&self, filter: Filter) -> Result<PropertyWithImages, ApiError>;
 
+    /// Property a former slug of the tenant now redirects to.
+    async fn find_slug_redirect(&self, tenant_id: i32, slug: &str)
+        -> Result<Option<i32>, ApiError>;
+
     async fn find_many(
         &self,
         filter: Filter,
         pagination: Pagination,
     ) -> Result<PaginatedRecord<PropertyWithImages>, ApiError>;
 
-    async fn delete(&self, id: i32, tenant_id: i32) -> Result<PropertyWithImages, ApiError>;
+    /// Properties matching `filter` and the free text `query`, most relevant first.
+    async fn search(
+        &self,
+        filter: Filter,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<PropertySearchHit>, ApiError>;
+
+    /// Streams every matching property without loading them all into memory.
+    fn stream(&self, filter: Filter) -> BoxStream<'static, Result<PropertyWithImages, ApiError>>;
+
+    async fn delete(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError>;
+
+    /// Takes the property out of the trash unless the tenant already has `max_active_listings`.
+    async fn restore(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        max_active_listings: Option<u32>,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError>;
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<PropertyWithImages>, ApiError>;
 }
 
 #[async_trait]
 pub trait BucketRepository: Send + Sync {
     async fn post_presigned_url(&self, key: &str) -> Result<String, ApiError>;
+    /// Deletes images given by bucket key or by the URL stored for them.
     async fn delete_images(&self, images: &[String]) -> Result<Vec<String>, ApiError>;
+    /// Downloads the image at `url` and stores it under `key`, returning its bucket URL.
+    async fn upload_from_url(&self, url: &str, key: &str) -> Result<String, ApiError>;
 }
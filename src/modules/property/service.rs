@@ -1,26 +1,47 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+
 use crate::{
     error::ApiError,
-    utils::database::{Filter, FilterCondition, PaginatedRecord, Pagination},
+    modules::{feed::FeedCache, plan},
+    utils::{
+        database::{Filter, FilterCondition, PaginatedRecord, Pagination},
+        etag::Precondition,
+    },
+};
+use futures::{
+    future::join_all,
+    stream::{self, BoxStream, StreamExt},
+    TryStreamExt,
 };
-use futures::stream::{self, StreamExt};
 use uuid::Uuid;
 
 use super::{
     port::{BucketRepository, DBRepository},
-    Property, PropertyImage, PropertyWithImages,
+    similarity::similarity,
+    ImportReport, ImportRow, ImportRowError, ImportedRow, Property, PropertyImage, PropertyPatch,
+    PropertySearchHit, PropertyWithImages, SlugLookup, SlugRedirect,
 };
 
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
     bucket_repo: Arc<dyn BucketRepository>,
+    plan_service: Arc<plan::Service>,
+    feed_cache: Arc<FeedCache>,
 }
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>, bucket_repo: Arc<dyn BucketRepository>) -> Self {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        bucket_repo: Arc<dyn BucketRepository>,
+        plan_service: Arc<plan::Service>,
+        feed_cache: Arc<FeedCache>,
+    ) -> Self {
         Self {
             db_repo,
             bucket_repo,
+            plan_service,
+            feed_cache,
         }
     }
 }
@@ -30,22 +51,71 @@ impl Service {
         &self,
         property: Property,
         images_urls: &[String],
+        actor_user_id: &str,
     ) -> Result<PropertyWithImages, ApiError> {
+        let limits = self
+            .plan_service
+            .find_tenant_limits(property.tenant_id)
+            .await?;
+        plan::check_limit(
+            "max_images_per_listing",
+            limits.max_images_per_listing,
+            images_urls.len(),
+        )?;
+
         let images: Vec<PropertyImage> = images_urls
             .into_iter()
             .map(|url| PropertyImage::new(property.id, &url, false))
             .collect();
 
-        self.db_repo.create(property, &images).await
+        let created_property = self
+            .db_repo
+            .create(property, &images, limits.max_active_listings, actor_user_id)
+            .await?;
+        self.feed_cache
+            .invalidate(created_property.property.tenant_id);
+
+        Ok(created_property)
     }
 
     pub async fn find_all_tenant_properties(
         &self,
         tenat_id: i32,
         pagination: Pagination,
+    ) -> Result<PaginatedRecord<PropertyWithImages>, ApiError> {
+        self.db_repo
+            .find_many(tenant_properties_filter(tenat_id), pagination)
+            .await
+    }
+
+    /// Full-text search over the tenant's listings, most relevant first.
+    pub async fn search_tenant_properties(
+        &self,
+        tenant_id: i32,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<PaginatedRecord<PropertySearchHit>, ApiError> {
+        self.db_repo
+            .search(tenant_properties_filter(tenant_id), query, pagination)
+            .await
+    }
+
+    /// Streams the same listings as `find_all_tenant_properties`, unpaginated.
+    pub fn export_tenant_properties(
+        &self,
+        tenant_id: i32,
+    ) -> BoxStream<'static, Result<PropertyWithImages, ApiError>> {
+        self.db_repo.stream(tenant_properties_filter(tenant_id))
+    }
+
+    pub async fn find_tenant_trash(
+        &self,
+        tenant_id: i32,
+        pagination: Pagination,
     ) -> Result<PaginatedRecord<PropertyWithImages>, ApiError> {
         let mut filter = Filter::new();
-        filter.add("tenant_id", FilterCondition::eq(tenat_id));
+        filter.add("tenant_id", FilterCondition::eq(tenant_id));
+        filter.add("deleted_at", FilterCondition::IsNotNull);
 
         self.db_repo.find_many(filter, pagination).await
     }
@@ -53,45 +123,187 @@ impl Service {
     pub async fn find_property_by_id(&self, id: i32) -> Result<PropertyWithImages, ApiError> {
         let mut filter = Filter::new();
         filter.add("id", FilterCondition::eq(id));
+        filter.add("deleted_at", FilterCondition::IsNull);
         self.db_repo.find(filter).await
     }
 
-    pub async fn delete_property(&self, id: i32, tenant_id: i32) -> Result<Property, ApiError> {
-        let deleted_property = self.db_repo.delete(id, tenant_id).await?;
-        let image_urls: Vec<String> = deleted_property
-            .images
+    /// Other listings of the same tenant most alike the given one, best match first.
+    pub async fn find_similar_properties(
+        &self,
+        id: i32,
+        limit: usize,
+    ) -> Result<Vec<PropertyWithImages>, ApiError> {
+        let property = self.find_property_by_id(id).await?;
+        let candidates: Vec<PropertyWithImages> = self
+            .export_tenant_properties(property.property.tenant_id)
+            .try_collect()
+            .await?;
+
+        let mut scored: Vec<(f64, PropertyWithImages)> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.property.id != id)
+            .map(|candidate| {
+                (
+                    similarity(&property.property, &candidate.property),
+                    candidate,
+                )
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|(a, a_listing), (b, b_listing)| {
+            b.total_cmp(a)
+                .then(a_listing.property.id.cmp(&b_listing.property.id))
+        });
+
+        Ok(scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, candidate)| candidate)
+            .collect())
+    }
+
+    /// Finds a listing by its current slug, or points to it when `slug` is a former one.
+    pub async fn find_property_by_slug(
+        &self,
+        tenant_id: i32,
+        slug: &str,
+    ) -> Result<SlugLookup, ApiError> {
+        let mut filter = tenant_properties_filter(tenant_id);
+        filter.add("slug", FilterCondition::eq(slug));
+        match self.db_repo.find(filter).await {
+            Ok(property) => return Ok(SlugLookup::Found(Box::new(property))),
+            Err(ApiError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let property_id = self
+            .db_repo
+            .find_slug_redirect(tenant_id, slug)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Property with slug {} not found", slug)))?;
+        let property = self.find_property_by_id(property_id).await?;
+
+        Ok(SlugLookup::Moved(SlugRedirect {
+            property_id,
+            slug: property.property.slug.unwrap_or_default(),
+        }))
+    }
+
+    /// Moves the property to the trash. Its images are kept until it is purged.
+    pub async fn delete_property(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Property, ApiError> {
+        let deleted_property = self
+            .db_repo
+            .delete(id, tenant_id, precondition, actor_user_id)
+            .await?;
+        self.feed_cache.invalidate(tenant_id);
+
+        Ok(deleted_property.property)
+    }
+
+    pub async fn restore_property(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
+        // Images were accepted when the listing was published; only the slot is re-checked.
+        let limits = self.plan_service.find_tenant_limits(tenant_id).await?;
+
+        let restored_property = self
+            .db_repo
+            .restore(id, tenant_id, limits.max_active_listings, actor_user_id)
+            .await?;
+        self.feed_cache.invalidate(tenant_id);
+
+        Ok(restored_property)
+    }
+
+    /// Permanently removes properties trashed before `deleted_before`, along with their images.
+    pub async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> Result<usize, ApiError> {
+        let purged_properties = self.db_repo.purge_deleted(deleted_before).await?;
+        let image_urls: Vec<String> = purged_properties
             .iter()
-            .map(|image| image.image_url.clone())
+            .flat_map(|purged| purged.images.iter().map(|image| image.image_url.clone()))
             .collect();
 
         //Todo: send the errors to a queue
-        if let Err(e) = self.bucket_repo.delete_images(&image_urls).await {
-            eprintln!("Failed to delete images from bucket: {:?}", e);
+        if !image_urls.is_empty() {
+            if let Err(e) = self.bucket_repo.delete_images(&image_urls).await {
+                log::error!("Failed to delete images from bucket: {:?}", e);
+            }
         }
 
-        Ok(deleted_property.property)
+        Ok(purged_properties.len())
     }
 
     pub async fn update_property(
         &self,
         property: Property,
         images_urls: &[String],
+        precondition: &Precondition,
+        actor_user_id: &str,
     ) -> Result<PropertyWithImages, ApiError> {
+        self.plan_service
+            .ensure_images_allowed(property.tenant_id, images_urls.len())
+            .await?;
+
         let images: Vec<PropertyImage> = images_urls
-            .into_iter()
-            .map(|url| PropertyImage::new(property.id, &url, false))
+            .iter()
+            .map(|url| PropertyImage::new(property.id, url, false))
             .collect();
-        let new_image = self
+
+        let new_property = self
             .db_repo
-            .edit_property_images(property.id, &images)
+            .update_property(property, &images, precondition, actor_user_id)
             .await?;
+        self.feed_cache.invalidate(new_property.property.tenant_id);
 
-        let new_property = self.db_repo.update_property(property).await?;
+        Ok(new_property)
+    }
 
-        Ok(PropertyWithImages {
-            property: new_property.property,
-            images: new_image,
-        })
+    /// Applies a partial update. Images are only replaced when `images_urls` is given.
+    pub async fn patch_property(
+        &self,
+        id: i32,
+        tenant_id: i32,
+        patch: PropertyPatch,
+        images_urls: Option<Vec<String>>,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<PropertyWithImages, ApiError> {
+        if let Some(images_urls) = &images_urls {
+            self.plan_service
+                .ensure_images_allowed(tenant_id, images_urls.len())
+                .await?;
+        }
+
+        let images: Option<Vec<PropertyImage>> = images_urls.map(|images_urls| {
+            images_urls
+                .iter()
+                .map(|url| PropertyImage::new(id, url, false))
+                .collect()
+        });
+
+        let patched_property = self
+            .db_repo
+            .patch_property(
+                id,
+                tenant_id,
+                patch,
+                images.as_deref(),
+                precondition,
+                actor_user_id,
+            )
+            .await?;
+        self.feed_cache.invalidate(tenant_id);
+
+        Ok(patched_property)
     }
 
     pub async fn generate_post_presigned_urls(
@@ -99,6 +311,10 @@ impl Service {
         tenant_id: i32,
         n_links: usize,
     ) -> Result<Vec<String>, ApiError> {
+        self.plan_service
+            .ensure_images_allowed(tenant_id, n_links)
+            .await?;
+
         let results: Vec<Result<String, ApiError>> = stream::iter(0..n_links)
             .map(|_| {
                 let key = format!("tenant_{}/properties/image_{}", tenant_id, Uuid::new_v4());
@@ -110,4 +326,131 @@ impl Service {
 
         results.into_iter().collect()
     }
+
+    /// Imports spreadsheet rows. Images are copied into the bucket and every
+    /// row whose images could be fetched is inserted in a single transaction.
+    /// With `dry_run` only the plan limits are checked and nothing is stored.
+    pub async fn import_properties(
+        &self,
+        tenant_id: i32,
+        rows: Vec<ImportRow>,
+        dry_run: bool,
+        actor_user_id: &str,
+    ) -> Result<ImportReport, ApiError> {
+        let max_images = rows.iter().map(|row| row.images_urls.len()).max();
+        self.plan_service
+            .ensure_listings_available(tenant_id, rows.len(), max_images.unwrap_or(0))
+            .await?;
+
+        let mut report = ImportReport {
+            dry_run,
+            total_rows: rows.len(),
+            valid_rows: rows.len(),
+            imported: Vec::new(),
+            errors: Vec::new(),
+        };
+        if dry_run || rows.is_empty() {
+            return Ok(report);
+        }
+
+        let uploads: Vec<Result<Vec<(String, String)>, ApiError>> = stream::iter(&rows)
+            .map(|row| self.upload_import_images(tenant_id, &row.images_urls))
+            .buffered(4)
+            .collect()
+            .await;
+
+        let mut batch = Vec::new();
+        let mut batch_rows = Vec::new();
+        let mut uploaded_keys = Vec::new();
+        for (row, upload) in rows.into_iter().zip(uploads) {
+            match upload {
+                Ok(uploaded) => {
+                    let images = uploaded
+                        .iter()
+                        .map(|(_, url)| PropertyImage::new(0, url, false))
+                        .collect();
+                    uploaded_keys.extend(uploaded.into_iter().map(|(key, _)| key));
+                    batch.push((row.property, images));
+                    batch_rows.push(row.row);
+                }
+                Err(e) => report.errors.push(ImportRowError {
+                    row: row.row,
+                    field: Some("images_urls".to_string()),
+                    message: match e {
+                        ApiError::BadRequest(message) => message,
+                        e => e.to_string(),
+                    },
+                }),
+            }
+        }
+        report.valid_rows = batch.len();
+
+        if batch.is_empty() {
+            return Ok(report);
+        }
+
+        let limits = self.plan_service.find_tenant_limits(tenant_id).await?;
+        let created = match self
+            .db_repo
+            .create_many(tenant_id, &batch, limits.max_active_listings, actor_user_id)
+            .await
+        {
+            Ok(created) => created,
+            Err(e) => {
+                //Todo: send the errors to a queue
+                if let Err(e) = self.bucket_repo.delete_images(&uploaded_keys).await {
+                    log::error!("Failed to delete imported images from bucket: {:?}", e);
+                }
+                return Err(e);
+            }
+        };
+        self.feed_cache.invalidate(tenant_id);
+
+        for (row, created_property) in batch_rows.into_iter().zip(created) {
+            report.imported.push(ImportedRow {
+                row,
+                property_id: created_property.property.id,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// Copies a row's images into the bucket, returning the key and URL of
+    /// each copy. The copies are deleted again if any of them fails.
+    async fn upload_import_images(
+        &self,
+        tenant_id: i32,
+        images_urls: &[String],
+    ) -> Result<Vec<(String, String)>, ApiError> {
+        let uploads = join_all(images_urls.iter().map(|url| {
+            let key = format!("tenant_{}/properties/image_{}", tenant_id, Uuid::new_v4());
+            async move {
+                let uploaded_url = self.bucket_repo.upload_from_url(url, &key).await?;
+                Ok::<_, ApiError>((key, uploaded_url))
+            }
+        }))
+        .await;
+
+        let (uploaded, failed): (Vec<_>, Vec<_>) = uploads.into_iter().partition(Result::is_ok);
+        let uploaded: Vec<(String, String)> = uploaded.into_iter().filter_map(Result::ok).collect();
+
+        if let Some(Err(e)) = failed.into_iter().next() {
+            //Todo: send the errors to a queue
+            let keys: Vec<String> = uploaded.into_iter().map(|(key, _)| key).collect();
+            if let Err(e) = self.bucket_repo.delete_images(&keys).await {
+                log::error!("Failed to delete imported images from bucket: {:?}", e);
+            }
+            return Err(e);
+        }
+
+        Ok(uploaded)
+    }
+}
+
+fn tenant_properties_filter(tenant_id: i32) -> Filter {
+    let mut filter = Filter::new();
+    filter.add("tenant_id", FilterCondition::eq(tenant_id));
+    filter.add("deleted_at", FilterCondition::IsNull);
+    filter
 }
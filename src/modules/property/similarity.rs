@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use crate::utils::slug::slugify;
+
+use super::Property;
+
+const PROPERTY_TYPE_WEIGHT: f64 = 0.30;
+const PRICE_WEIGHT: f64 = 0.25;
+const BEDROOMS_WEIGHT: f64 = 0.15;
+const AREA_WEIGHT: f64 = 0.10;
+const LOCATION_WEIGHT: f64 = 0.10;
+const AMENITIES_WEIGHT: f64 = 0.10;
+
+/// How alike `candidate` is to `property`, from 0 (nothing in common) to 1.
+///
+/// Prices are only compared within the same currency, and location is judged
+/// by city, then state, then country since listings carry no coordinates.
+pub fn similarity(property: &Property, candidate: &Property) -> f64 {
+    let same_type = normalize(&property.property_type) == normalize(&candidate.property_type);
+    let price = if normalize(&property.currency) == normalize(&candidate.currency) {
+        closeness(Some(property.price), Some(candidate.price))
+    } else {
+        0.0
+    };
+
+    PROPERTY_TYPE_WEIGHT * f64::from(u8::from(same_type))
+        + PRICE_WEIGHT * price
+        + BEDROOMS_WEIGHT * bedrooms(property.bedrooms, candidate.bedrooms)
+        + AREA_WEIGHT * closeness(area(property), area(candidate))
+        + LOCATION_WEIGHT * location(property, candidate)
+        + AMENITIES_WEIGHT * amenities(&property.amenities, &candidate.amenities)
+}
+
+/// 1 for equal amounts, falling linearly to 0 once one is half the other.
+fn closeness(a: Option<f64>, b: Option<f64>) -> f64 {
+    match (a, b) {
+        (Some(a), Some(b)) if a > 0.0 && b > 0.0 => {
+            let ratio = a.min(b) / a.max(b);
+            ((ratio - 0.5) / 0.5).clamp(0.0, 1.0)
+        }
+        _ => 0.0,
+    }
+}
+
+fn bedrooms(a: Option<i32>, b: Option<i32>) -> f64 {
+    match (a, b) {
+        (Some(a), Some(b)) => match (a - b).abs() {
+            0 => 1.0,
+            1 => 0.5,
+            _ => 0.0,
+        },
+        _ => 0.0,
+    }
+}
+
+fn area(property: &Property) -> Option<f64> {
+    property.built_area.or(property.total_area)
+}
+
+fn location(a: &Property, b: &Property) -> f64 {
+    let same = |x: &Option<String>, y: &Option<String>| match (x, y) {
+        (Some(x), Some(y)) => normalize(x) == normalize(y),
+        _ => false,
+    };
+
+    if same(&a.city, &b.city) {
+        1.0
+    } else if same(&a.state, &b.state) {
+        0.5
+    } else if same(&a.country, &b.country) {
+        0.2
+    } else {
+        0.0
+    }
+}
+
+/// Jaccard index of the two amenity sets.
+fn amenities(a: &Option<Vec<String>>, b: &Option<Vec<String>>) -> f64 {
+    let set = |amenities: &Option<Vec<String>>| -> HashSet<String> {
+        amenities
+            .iter()
+            .flatten()
+            .map(|amenity| normalize(amenity))
+            .collect()
+    };
+    let (a, b) = (set(a), set(b));
+
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Folds case, accents and punctuation so `San Isidro` matches `san isidro`.
+fn normalize(value: &str) -> String {
+    slugify(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(property_type: &str, price: f64, currency: &str) -> Property {
+        let mut property = Property::new(
+            1,
+            "Listing",
+            None::<&str>,
+            property_type,
+            "for_sale",
+            price,
+            currency,
+            Some(3),
+            Some(2),
+            None,
+            None,
+            Some(120.0),
+            None,
+            None,
+            Some("Miraflores"),
+            Some("Lima"),
+            Some("Peru"),
+            Some(vec!["piscina".to_string(), "gimnasio".to_string()]),
+            None,
+        );
+        property.id = 1;
+        property
+    }
+
+    #[test]
+    fn identical_listings_score_one() {
+        let property = listing("departamento", 250_000.0, "USD");
+
+        assert!((similarity(&property, &property.clone()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn prices_in_other_currencies_are_not_compared() {
+        let property = listing("departamento", 250_000.0, "USD");
+        let same_currency = listing("departamento", 250_000.0, "USD");
+        let other_currency = listing("departamento", 250_000.0, "PEN");
+
+        let difference =
+            similarity(&property, &same_currency) - similarity(&property, &other_currency);
+        assert!((difference - PRICE_WEIGHT).abs() < 1e-9);
+    }
+
+    #[test]
+    fn closer_prices_score_higher() {
+        let property = listing("departamento", 250_000.0, "USD");
+        let close = listing("departamento", 260_000.0, "USD");
+        let far = listing("departamento", 400_000.0, "USD");
+        let too_far = listing("departamento", 600_000.0, "USD");
+
+        assert!(similarity(&property, &close) > similarity(&property, &far));
+        assert!(similarity(&property, &far) > similarity(&property, &too_far));
+        assert_eq!(closeness(Some(250_000.0), Some(600_000.0)), 0.0);
+    }
+
+    #[test]
+    fn property_type_is_compared_ignoring_case_and_accents() {
+        let property = listing("Departamento", 250_000.0, "USD");
+        let same_type = listing("departamento ", 250_000.0, "usd");
+        let other_type = listing("casa", 250_000.0, "USD");
+
+        assert!((similarity(&property, &same_type) - 1.0).abs() < 1e-9);
+        assert!((similarity(&property, &other_type) - (1.0 - PROPERTY_TYPE_WEIGHT)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bedrooms_one_apart_score_half() {
+        assert_eq!(bedrooms(Some(3), Some(3)), 1.0);
+        assert_eq!(bedrooms(Some(3), Some(2)), 0.5);
+        assert_eq!(bedrooms(Some(3), Some(5)), 0.0);
+        assert_eq!(bedrooms(Some(3), None), 0.0);
+    }
+
+    #[test]
+    fn nearer_locations_score_higher() {
+        let property = listing("departamento", 250_000.0, "USD");
+
+        let mut same_city = listing("departamento", 250_000.0, "USD");
+        same_city.city = Some("miraflores".to_string());
+        let mut same_state = same_city.clone();
+        same_state.city = Some("San Isidro".to_string());
+        let mut same_country = same_state.clone();
+        same_country.state = Some("Arequipa".to_string());
+        let mut elsewhere = same_country.clone();
+        elsewhere.country = Some("Chile".to_string());
+
+        assert_eq!(location(&property, &same_city), 1.0);
+        assert_eq!(location(&property, &same_state), 0.5);
+        assert_eq!(location(&property, &same_country), 0.2);
+        assert_eq!(location(&property, &elsewhere), 0.0);
+    }
+
+    #[test]
+    fn amenity_overlap_is_the_jaccard_index() {
+        let a = Some(vec!["Piscina".to_string(), "Gimnasio".to_string()]);
+        let b = Some(vec!["piscina".to_string(), "Terraza".to_string()]);
+
+        assert!((amenities(&a, &b) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(amenities(&None, &None), 0.0);
+    }
+
+    #[test]
+    fn missing_details_earn_no_credit() {
+        let property = listing("departamento", 250_000.0, "USD");
+        let mut sparse = listing("departamento", 250_000.0, "USD");
+        sparse.bedrooms = None;
+        sparse.built_area = None;
+        sparse.city = None;
+        sparse.state = None;
+        sparse.country = None;
+        sparse.amenities = None;
+
+        let expected = PROPERTY_TYPE_WEIGHT + PRICE_WEIGHT;
+        assert!((similarity(&property, &sparse) - expected).abs() < 1e-9);
+    }
+}
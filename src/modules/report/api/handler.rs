@@ -0,0 +1,131 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::{
+    error::ApiError,
+    modules::{
+        report::{render, PreviewFormat, Service},
+        tenant,
+    },
+    utils::lucia,
+};
+
+#[derive(Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub weekly_report: bool,
+}
+
+pub async fn get_preferences(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let preferences = service.find_preferences(tenant.id).await?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+pub async fn update_preferences(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Json(request): web::Json<UpdatePreferencesRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let preferences = service
+        .update_preferences(tenant.id, request.weekly_report)
+        .await?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UnsubscribeQuery {
+    #[validate(length(min = 1, max = 64))]
+    pub token: String,
+}
+
+/// Target of the link in the emails, so it works without signing in. Only
+/// asks for confirmation, as mail scanners open links.
+pub async fn unsubscribe_page(
+    web::Query(query): web::Query<UnsubscribeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render::unsubscribe_page(&query.token)))
+}
+
+/// Turns the report off, from the confirmation page or a mail client's
+/// one-click unsubscribe (RFC 8058), which posts `List-Unsubscribe=One-Click`.
+pub async fn unsubscribe(
+    service: web::Data<Arc<Service>>,
+    web::Query(query): web::Query<UnsubscribeQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    service.unsubscribe(&query.token).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render::unsubscribed_page()))
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    /// Any day of the week to report on; defaults to the last complete week.
+    pub week: Option<NaiveDate>,
+    #[serde(default)]
+    pub format: PreviewFormat,
+}
+
+/// Renders the weekly report email of the signed-in tenant without sending it.
+pub async fn preview_weekly_report(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<PreviewQuery>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let email = service.preview(tenant.id, query.week).await?;
+    let response = match query.format {
+        PreviewFormat::Html => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .insert_header(("X-Email-Subject", email.subject))
+            .body(email.html),
+        PreviewFormat::Text => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .insert_header(("X-Email-Subject", email.subject))
+            .body(email.text),
+    };
+    Ok(response)
+}
@@ -0,0 +1,17 @@
+use actix_web::web;
+use handler::{
+    get_preferences, preview_weekly_report, unsubscribe, unsubscribe_page, update_preferences,
+};
+
+mod handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/reports")
+            .route("/preferences", web::get().to(get_preferences))
+            .route("/preferences", web::put().to(update_preferences))
+            .route("/unsubscribe", web::get().to(unsubscribe_page))
+            .route("/unsubscribe", web::post().to(unsubscribe))
+            .route("/weekly/preview", web::get().to(preview_weekly_report)),
+    );
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde_json::json;
+
+use crate::{
+    error::ApiError,
+    modules::report::{port::Mailer, Email},
+};
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends through a transactional email API taking a JSON body with `from`,
+/// `to`, `subject`, `html`, `text` and extra `headers`, authenticated with a
+/// bearer key.
+pub struct HttpMailer {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    from: String,
+}
+
+impl HttpMailer {
+    pub fn new(url: String, api_key: Option<String>, from: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            api_key,
+            from,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for HttpMailer {
+    async fn send(&self, email: &Email) -> Result<(), ApiError> {
+        let mut body = json!({
+            "from": self.from,
+            "to": [email.to],
+            "subject": email.subject,
+            "html": email.html,
+            "text": email.text,
+        });
+        // One-click unsubscribe as in RFC 8058: clients POST to the URL.
+        if let Some(unsubscribe_url) = &email.unsubscribe_url {
+            body["headers"] = json!({
+                "List-Unsubscribe": format!("<{}>", unsubscribe_url),
+                "List-Unsubscribe-Post": "List-Unsubscribe=One-Click",
+            });
+        }
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(SEND_TIMEOUT)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        if let Some(api_key) = &self.api_key {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", api_key));
+        }
+
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ApiError::ServiceUnavailable(format!("Could not send email: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Logs emails instead of sending them, for environments without a mailer.
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<(), ApiError> {
+        log::info!(
+            "Email to {} not sent, no mailer configured: {}",
+            email.to,
+            email.subject
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+mod mailer;
+pub use mailer::*;
+
+mod pg_adapter;
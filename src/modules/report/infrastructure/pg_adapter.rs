@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use crate::{
+    error::ApiError,
+    modules::report::{port::DBRepository, Recipient, ReportPreferences},
+    utils::database::PostgresRepository,
+};
+
+#[async_trait]
+impl DBRepository for PostgresRepository {
+    async fn find_preferences(&self, tenant_id: i32) -> Result<ReportPreferences, ApiError> {
+        // The select does not see the row inserted by the same statement, so
+        // exactly one of both returns it.
+        sqlx::query_as::<_, ReportPreferences>(
+            r#"
+            WITH inserted AS (
+                INSERT INTO report_preferences (tenant_id)
+                VALUES ($1)
+                ON CONFLICT (tenant_id) DO NOTHING
+                RETURNING tenant_id, weekly_report, unsubscribe_token, updated_at
+            )
+            SELECT tenant_id, weekly_report, unsubscribe_token, updated_at FROM inserted
+            UNION ALL
+            SELECT tenant_id, weekly_report, unsubscribe_token, updated_at
+            FROM report_preferences
+            WHERE tenant_id = $1
+            "#,
+        )
+        .bind(tenant_id)
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_error) if db_error.is_foreign_key_violation() => {
+                ApiError::NotFound(format!("Tenant with id {} not found", tenant_id))
+            }
+            _ => ApiError::DatabaseError(e),
+        })
+    }
+
+    async fn update_preferences(
+        &self,
+        tenant_id: i32,
+        weekly_report: bool,
+    ) -> Result<ReportPreferences, ApiError> {
+        sqlx::query_as::<_, ReportPreferences>(
+            r#"
+            INSERT INTO report_preferences (tenant_id, weekly_report)
+            VALUES ($1, $2)
+            ON CONFLICT (tenant_id) DO UPDATE
+            SET weekly_report = EXCLUDED.weekly_report,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING tenant_id, weekly_report, unsubscribe_token, updated_at
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(weekly_report)
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_error) if db_error.is_foreign_key_violation() => {
+                ApiError::NotFound(format!("Tenant with id {} not found", tenant_id))
+            }
+            _ => ApiError::DatabaseError(e),
+        })
+    }
+
+    async fn unsubscribe(&self, token: &str) -> Result<ReportPreferences, ApiError> {
+        sqlx::query_as::<_, ReportPreferences>(
+            r#"
+            UPDATE report_preferences
+            SET weekly_report = FALSE,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE unsubscribe_token = $1
+            RETURNING tenant_id, weekly_report, unsubscribe_token, updated_at
+            "#,
+        )
+        .bind(token)
+        .fetch_optional(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?
+        .ok_or_else(|| ApiError::NotFound("Unknown unsubscribe token".into()))
+    }
+
+    async fn find_recipient(&self, tenant_id: i32) -> Result<Recipient, ApiError> {
+        self.find_preferences(tenant_id).await?;
+
+        sqlx::query_as::<_, Recipient>(
+            r#"
+            SELECT t.id AS tenant_id, u.email, t.first_name, t.company_name, p.unsubscribe_token
+            FROM tenants t
+            JOIN auth_user u ON u.id = t.auth_user_id
+            JOIN report_preferences p ON p.tenant_id = t.id
+            WHERE t.id = $1
+            "#,
+        )
+        .bind(tenant_id)
+        .fetch_optional(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?
+        .ok_or_else(|| ApiError::NotFound(format!("Tenant with id {} not found", tenant_id)))
+    }
+
+    async fn due_recipients(&self, week_start: NaiveDate) -> Result<Vec<Recipient>, ApiError> {
+        // Tenants created since the last run get their defaults first.
+        sqlx::query(
+            "INSERT INTO report_preferences (tenant_id)
+             SELECT id FROM tenants
+             ON CONFLICT (tenant_id) DO NOTHING",
+        )
+        .execute(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        sqlx::query_as::<_, Recipient>(
+            r#"
+            SELECT t.id AS tenant_id, u.email, t.first_name, t.company_name, p.unsubscribe_token
+            FROM tenants t
+            JOIN auth_user u ON u.id = t.auth_user_id
+            JOIN report_preferences p ON p.tenant_id = t.id
+            WHERE p.weekly_report
+            AND NOT EXISTS (
+                SELECT 1 FROM weekly_reports_sent r
+                WHERE r.tenant_id = t.id AND r.week_start = $1
+            )
+            ORDER BY t.id
+            "#,
+        )
+        .bind(week_start)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)
+    }
+
+    async fn mark_sent(&self, tenant_id: i32, week_start: NaiveDate) -> Result<(), ApiError> {
+        sqlx::query(
+            "INSERT INTO weekly_reports_sent (tenant_id, week_start)
+             VALUES ($1, $2)
+             ON CONFLICT (tenant_id, week_start) DO NOTHING",
+        )
+        .bind(tenant_id)
+        .bind(week_start)
+        .execute(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+pub mod port;
+
+mod model;
+pub use model::*;
+
+pub mod infrastructure;
+
+mod render;
+
+mod service;
+pub use service::*;
+
+mod api;
+pub use api::*;
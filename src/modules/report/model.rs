@@ -0,0 +1,83 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReportPreferences {
+    pub tenant_id: i32,
+    pub weekly_report: bool,
+    #[serde(skip_serializing)]
+    pub unsubscribe_token: String,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Tenant a report is emailed to, at the address it signs in with.
+#[derive(Debug, Clone, FromRow)]
+pub struct Recipient {
+    pub tenant_id: i32,
+    pub email: String,
+    pub first_name: String,
+    pub company_name: Option<String>,
+    pub unsubscribe_token: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopProperty {
+    pub property_id: i32,
+    pub title: String,
+    pub visits: i64,
+}
+
+/// Source that brought the most leads, or visits when there were none.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopSource {
+    pub source: String,
+    pub medium: String,
+    pub visits: i64,
+    pub leads: i64,
+}
+
+/// A tenant's performance over a week, Monday to Sunday in UTC.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklyReport {
+    pub tenant_id: i32,
+    pub week_start: NaiveDate,
+    pub week_end: NaiveDate,
+    /// Landing and listing page views.
+    pub visits: i64,
+    pub previous_visits: i64,
+    /// Relative change of the visits against the week before, in percent.
+    pub visits_change_pct: Option<f64>,
+    pub leads: i64,
+    /// WhatsApp and phone button clicks.
+    pub contacts: i64,
+    pub top_properties: Vec<TopProperty>,
+    pub best_referrer: Option<TopSource>,
+}
+
+impl WeeklyReport {
+    /// Whether nothing happened during the week, in which case no email is sent.
+    pub fn is_empty(&self) -> bool {
+        self.visits == 0 && self.leads == 0 && self.contacts == 0
+    }
+}
+
+/// A message with its HTML and plain text alternatives.
+#[derive(Debug, Clone, Serialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    /// Sent as `List-Unsubscribe`, so mail clients offer one-click unsubscribe.
+    pub unsubscribe_url: Option<String>,
+}
+
+/// Representation a report preview is returned in.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewFormat {
+    #[default]
+    Html,
+    Text,
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use crate::error::ApiError;
+
+use super::{Email, Recipient, ReportPreferences};
+
+#[async_trait]
+pub trait DBRepository: Send + Sync {
+    /// Preferences of the tenant, created with the defaults if it has none.
+    async fn find_preferences(&self, tenant_id: i32) -> Result<ReportPreferences, ApiError>;
+
+    async fn update_preferences(
+        &self,
+        tenant_id: i32,
+        weekly_report: bool,
+    ) -> Result<ReportPreferences, ApiError>;
+
+    /// Turns the weekly report off for the tenant owning `token`.
+    async fn unsubscribe(&self, token: &str) -> Result<ReportPreferences, ApiError>;
+
+    async fn find_recipient(&self, tenant_id: i32) -> Result<Recipient, ApiError>;
+
+    /// Tenants receiving the weekly report that were not sent the one for
+    /// the week starting on `week_start` yet.
+    async fn due_recipients(&self, week_start: NaiveDate) -> Result<Vec<Recipient>, ApiError>;
+
+    async fn mark_sent(&self, tenant_id: i32, week_start: NaiveDate) -> Result<(), ApiError>;
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), ApiError>;
+}
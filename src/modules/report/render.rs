@@ -0,0 +1,197 @@
+use std::fmt::Write;
+
+use chrono::{Datelike, NaiveDate};
+use url::form_urlencoded::byte_serialize;
+
+use crate::utils::xml::escape;
+
+use super::{Email, Recipient, WeeklyReport};
+
+/// Renders the weekly report email. `unsubscribe_url` is the link turning the
+/// report off, when the API's public URL is known.
+pub fn weekly_email(
+    recipient: &Recipient,
+    report: &WeeklyReport,
+    unsubscribe_url: Option<&str>,
+) -> Email {
+    Email {
+        to: recipient.email.clone(),
+        subject: format!(
+            "Your week: {} {} and {} {} ({})",
+            report.visits,
+            plural(report.visits, "visit", "visits"),
+            report.leads,
+            plural(report.leads, "lead", "leads"),
+            week(report.week_start, report.week_end)
+        ),
+        html: html(recipient, report, unsubscribe_url),
+        text: text(recipient, report, unsubscribe_url),
+        unsubscribe_url: unsubscribe_url.map(str::to_string),
+    }
+}
+
+/// Page the unsubscribe link opens. Unsubscribing takes a POST, so link
+/// scanners following the link do not turn the report off on their own.
+pub fn unsubscribe_page(token: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<body style=\"font-family: Arial, sans-serif; color: #1f2937;\">\n\
+         <p>Stop receiving the weekly report?</p>\n\
+         <form method=\"post\" action=\"unsubscribe?token={}\">\n\
+         <button type=\"submit\">Unsubscribe</button>\n\
+         </form>\n</body>\n</html>\n",
+        escape(&byte_serialize(token.as_bytes()).collect::<String>())
+    )
+}
+
+pub fn unsubscribed_page() -> String {
+    "<!DOCTYPE html>\n<html>\n<body style=\"font-family: Arial, sans-serif; color: #1f2937;\">\n\
+     <p>You will no longer receive the weekly report.</p>\n</body>\n</html>\n"
+        .to_string()
+}
+
+fn html(recipient: &Recipient, report: &WeeklyReport, unsubscribe_url: Option<&str>) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<body style=\"font-family: Arial, sans-serif; color: #1f2937;\">\n",
+    );
+    let _ = writeln!(html, "<p>Hi {},</p>", escape(&recipient.first_name));
+    let _ = writeln!(
+        html,
+        "<p>This is how {} did from {}.</p>",
+        escape(site_name(recipient)),
+        week(report.week_start, report.week_end)
+    );
+
+    html.push_str("<table cellpadding=\"8\" style=\"border-collapse: collapse;\">\n");
+    metric_row(
+        &mut html,
+        "Visits",
+        &format!("{}{}", report.visits, change(report.visits_change_pct)),
+    );
+    metric_row(&mut html, "Leads", &report.leads.to_string());
+    metric_row(&mut html, "Contact clicks", &report.contacts.to_string());
+    if let Some(source) = &report.best_referrer {
+        metric_row(
+            &mut html,
+            "Best source",
+            &format!(
+                "{} / {} ({} visits, {} leads)",
+                source.source, source.medium, source.visits, source.leads
+            ),
+        );
+    }
+    html.push_str("</table>\n");
+
+    if !report.top_properties.is_empty() {
+        html.push_str("<h3>Most visited listings</h3>\n<ol>\n");
+        for property in &report.top_properties {
+            let _ = writeln!(
+                html,
+                "<li>{} &mdash; {} {}</li>",
+                escape(&property.title),
+                property.visits,
+                plural(property.visits, "visit", "visits")
+            );
+        }
+        html.push_str("</ol>\n");
+    }
+
+    if let Some(unsubscribe_url) = unsubscribe_url {
+        let _ = writeln!(
+            html,
+            "<p style=\"font-size: 12px; color: #6b7280;\">You get this email every Monday. \
+             <a href=\"{}\">Unsubscribe</a></p>",
+            escape(unsubscribe_url)
+        );
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn text(recipient: &Recipient, report: &WeeklyReport, unsubscribe_url: Option<&str>) -> String {
+    let mut text = format!("Hi {},\n\n", recipient.first_name);
+    let _ = writeln!(
+        text,
+        "This is how {} did from {}.\n",
+        site_name(recipient),
+        week(report.week_start, report.week_end)
+    );
+    let _ = writeln!(
+        text,
+        "Visits: {}{}",
+        report.visits,
+        change(report.visits_change_pct)
+    );
+    let _ = writeln!(text, "Leads: {}", report.leads);
+    let _ = writeln!(text, "Contact clicks: {}", report.contacts);
+    if let Some(source) = &report.best_referrer {
+        let _ = writeln!(
+            text,
+            "Best source: {} / {} ({} visits, {} leads)",
+            source.source, source.medium, source.visits, source.leads
+        );
+    }
+
+    if !report.top_properties.is_empty() {
+        text.push_str("\nMost visited listings:\n");
+        for (i, property) in report.top_properties.iter().enumerate() {
+            let _ = writeln!(
+                text,
+                "{}. {} - {} {}",
+                i + 1,
+                property.title,
+                property.visits,
+                plural(property.visits, "visit", "visits")
+            );
+        }
+    }
+
+    if let Some(unsubscribe_url) = unsubscribe_url {
+        let _ = write!(
+            text,
+            "\nYou get this email every Monday. Unsubscribe: {}\n",
+            unsubscribe_url
+        );
+    }
+    text
+}
+
+fn metric_row(html: &mut String, label: &str, value: &str) {
+    let _ = writeln!(
+        html,
+        "<tr><td>{}</td><td style=\"font-weight: bold;\">{}</td></tr>",
+        escape(label),
+        escape(value)
+    );
+}
+
+fn site_name(recipient: &Recipient) -> &str {
+    recipient
+        .company_name
+        .as_deref()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or("your site")
+}
+
+/// `6 to 12 Oct 2026`, or with both months when the week spans two.
+fn week(start: NaiveDate, end: NaiveDate) -> String {
+    if (start.year(), start.month()) == (end.year(), end.month()) {
+        format!("{} to {}", start.format("%-d"), end.format("%-d %b %Y"))
+    } else {
+        format!("{} to {}", start.format("%-d %b"), end.format("%-d %b %Y"))
+    }
+}
+
+fn change(change_pct: Option<f64>) -> String {
+    match change_pct {
+        Some(change_pct) => format!(" ({:+.0}% on the week before)", change_pct),
+        None => String::new(),
+    }
+}
+
+fn plural(count: i64, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 {
+        one
+    } else {
+        many
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use url::form_urlencoded::byte_serialize;
+
+use crate::{
+    error::ApiError,
+    modules::{
+        property,
+        stats::{self, AttributionGrouping, EventType, PeriodQuery},
+    },
+};
+
+use super::{
+    port::{DBRepository, Mailer},
+    render, Email, ReportPreferences, TopProperty, TopSource, WeeklyReport,
+};
+
+/// Listings named in a weekly report, most visited first.
+const TOP_PROPERTIES: usize = 5;
+/// Sources that say nothing about which channel works.
+const UNATTRIBUTED_SOURCES: [&str; 2] = ["direct", "(not set)"];
+
+pub struct Service {
+    db_repo: Arc<dyn DBRepository>,
+    mailer: Arc<dyn Mailer>,
+    stats_service: Arc<stats::Service>,
+    property_service: Arc<property::Service>,
+    /// Public URL of the API, to link emails to the unsubscribe endpoint.
+    public_api_url: Option<String>,
+}
+
+impl Service {
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        mailer: Arc<dyn Mailer>,
+        stats_service: Arc<stats::Service>,
+        property_service: Arc<property::Service>,
+        public_api_url: Option<String>,
+    ) -> Self {
+        Self {
+            db_repo,
+            mailer,
+            stats_service,
+            property_service,
+            public_api_url,
+        }
+    }
+
+    pub async fn find_preferences(&self, tenant_id: i32) -> Result<ReportPreferences, ApiError> {
+        self.db_repo.find_preferences(tenant_id).await
+    }
+
+    pub async fn update_preferences(
+        &self,
+        tenant_id: i32,
+        weekly_report: bool,
+    ) -> Result<ReportPreferences, ApiError> {
+        self.db_repo
+            .update_preferences(tenant_id, weekly_report)
+            .await
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> Result<ReportPreferences, ApiError> {
+        self.db_repo.unsubscribe(token).await
+    }
+
+    /// The weekly email the tenant would get for the week containing `day`,
+    /// the last complete week by default.
+    pub async fn preview(&self, tenant_id: i32, day: Option<NaiveDate>) -> Result<Email, ApiError> {
+        let today = Utc::now().date_naive();
+        let week_start = match day {
+            Some(day) if day > today => {
+                return Err(ApiError::BadRequest(
+                    "Reports can only be previewed for past weeks".into(),
+                ))
+            }
+            Some(day) => week_start(day),
+            None => week_start(today) - Duration::days(7),
+        };
+
+        let recipient = self.db_repo.find_recipient(tenant_id).await?;
+        let report = self.weekly_report(tenant_id, week_start).await?;
+        Ok(render::weekly_email(
+            &recipient,
+            &report,
+            self.unsubscribe_url(&recipient.unsubscribe_token)
+                .as_deref(),
+        ))
+    }
+
+    /// Emails the report of the last complete week to every tenant due one,
+    /// returning how many were sent. A failed email is retried on the next run.
+    pub async fn send_weekly_reports(&self) -> Result<usize, ApiError> {
+        let week_start = week_start(Utc::now().date_naive()) - Duration::days(7);
+        let mut sent = 0;
+
+        for recipient in self.db_repo.due_recipients(week_start).await? {
+            let report = match self.weekly_report(recipient.tenant_id, week_start).await {
+                Ok(report) => report,
+                Err(e) => {
+                    log::error!(
+                        "Failed to compute the weekly report of tenant {}: {}",
+                        recipient.tenant_id,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            // Quiet weeks are skipped, but still marked so they are not recomputed.
+            if !report.is_empty() {
+                let email = render::weekly_email(
+                    &recipient,
+                    &report,
+                    self.unsubscribe_url(&recipient.unsubscribe_token)
+                        .as_deref(),
+                );
+                if let Err(e) = self.mailer.send(&email).await {
+                    log::error!(
+                        "Failed to send the weekly report to tenant {}: {}",
+                        recipient.tenant_id,
+                        e
+                    );
+                    continue;
+                }
+                sent += 1;
+            }
+
+            // Not marking it only risks sending the report again next run.
+            if let Err(e) = self
+                .db_repo
+                .mark_sent(recipient.tenant_id, week_start)
+                .await
+            {
+                log::error!(
+                    "Failed to mark the weekly report of tenant {} as sent: {}",
+                    recipient.tenant_id,
+                    e
+                );
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Metrics of the tenant over the week starting on `week_start`, bots left out.
+    pub async fn weekly_report(
+        &self,
+        tenant_id: i32,
+        week_start: NaiveDate,
+    ) -> Result<WeeklyReport, ApiError> {
+        let period = |from: NaiveDate| PeriodQuery {
+            tenant_id,
+            timezone: "UTC".into(),
+            from: from.and_hms_opt(0, 0, 0).unwrap_or_default(),
+            to: (from + Duration::days(7))
+                .and_hms_opt(0, 0, 0)
+                .unwrap_or_default(),
+            include_bots: false,
+        };
+        let query = period(week_start);
+
+        let overview = self
+            .stats_service
+            .get_events_overview(query.clone())
+            .await?;
+        let previous = self
+            .stats_service
+            .get_events_overview(period(week_start - Duration::days(7)))
+            .await?;
+        let count = |overview: &stats::EventsOverview, event_types: &[EventType]| -> i64 {
+            overview
+                .events
+                .iter()
+                .filter(|totals| event_types.contains(&totals.event_type))
+                .map(|totals| totals.events)
+                .sum()
+        };
+        let visit_types = [EventType::LandingVisited, EventType::PropertyVisited];
+        let visits = count(&overview, &visit_types);
+        let previous_visits = count(&previous, &visit_types);
+
+        let top_properties = self.top_properties(query.clone()).await?;
+        let best_referrer = self
+            .stats_service
+            .get_attribution_report(query, AttributionGrouping::Source)
+            .await?
+            .rows
+            .into_iter()
+            .filter(|row| !UNATTRIBUTED_SOURCES.contains(&row.source.as_str()))
+            .max_by_key(|row| (row.leads, row.visits))
+            .map(|row| TopSource {
+                source: row.source,
+                medium: row.medium,
+                visits: row.visits,
+                leads: row.leads,
+            });
+
+        Ok(WeeklyReport {
+            tenant_id,
+            week_start,
+            week_end: week_start + Duration::days(6),
+            visits,
+            previous_visits,
+            visits_change_pct: (previous_visits > 0)
+                .then(|| (visits - previous_visits) as f64 / previous_visits as f64 * 100.0),
+            leads: count(&overview, &[EventType::LeadFormSubmitted]),
+            contacts: count(&overview, &[EventType::ContactClicked]),
+            top_properties,
+            best_referrer,
+        })
+    }
+
+    /// Most visited listings still published, with their titles.
+    async fn top_properties(&self, query: PeriodQuery) -> Result<Vec<TopProperty>, ApiError> {
+        let report = self
+            .stats_service
+            .get_event_type_report(query, EventType::PropertyVisited)
+            .await?;
+
+        let mut top_properties = Vec::new();
+        for entry in report.breakdown {
+            let Some(property_id) = entry.key.and_then(|key| key.parse::<i32>().ok()) else {
+                continue;
+            };
+            match self.property_service.find_property_by_id(property_id).await {
+                Ok(property) => top_properties.push(TopProperty {
+                    property_id,
+                    title: property.property.title,
+                    visits: entry.events,
+                }),
+                Err(ApiError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+            if top_properties.len() == TOP_PROPERTIES {
+                break;
+            }
+        }
+        Ok(top_properties)
+    }
+
+    fn unsubscribe_url(&self, token: &str) -> Option<String> {
+        self.public_api_url.as_ref().map(|url| {
+            format!(
+                "{}/v2/reports/unsubscribe?token={}",
+                url.trim_end_matches('/'),
+                byte_serialize(token.as_bytes()).collect::<String>()
+            )
+        })
+    }
+}
+
+/// Monday of the week containing `day`.
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday().into())
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use crate::{error::ApiError, modules::site::Service, utils::etag::conditional_response};
+
+const SITE_MAX_AGE_SECS: u32 = 60;
+
+pub async fn get_site(
+    service: web::Data<Arc<Service>>,
+    tenant_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let site = service.find_tenant_site(*tenant_id).await?;
+    let body = serde_json::to_vec(&site)?;
+
+    Ok(conditional_response(
+        &req_headers,
+        body,
+        "application/json",
+        SITE_MAX_AGE_SECS,
+    ))
+}
+
+pub async fn get_sitemap(
+    service: web::Data<Arc<Service>>,
+    tenant_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let sitemap = service.find_tenant_sitemap(*tenant_id).await?;
+
+    Ok(conditional_response(
+        &req_headers,
+        sitemap.into_bytes(),
+        "application/xml; charset=utf-8",
+        SITE_MAX_AGE_SECS,
+    ))
+}
+
+pub async fn get_robots(
+    service: web::Data<Arc<Service>>,
+    tenant_id: web::Path<i32>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let robots = service.find_tenant_robots(*tenant_id)?;
+
+    Ok(conditional_response(
+        &req_headers,
+        robots.into_bytes(),
+        "text/plain; charset=utf-8",
+        SITE_MAX_AGE_SECS,
+    ))
+}
@@ -0,0 +1,13 @@
+use actix_web::web;
+use handler::{get_robots, get_site, get_sitemap};
+
+mod handler;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sites")
+            .route("/{tenant_id}", web::get().to(get_site))
+            .route("/{tenant_id}/sitemap.xml", web::get().to(get_sitemap))
+            .route("/{tenant_id}/robots.txt", web::get().to(get_robots)),
+    );
+}
@@ -0,0 +1,8 @@
+mod api;
+pub use api::*;
+
+mod model;
+pub use model::*;
+
+mod service;
+pub use service::*;
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+use crate::{
+    modules::{
+        front::{
+            landing::{config::Config, feedback::Feedback, hero::Hero},
+            social_media::SocialMedia,
+        },
+        property::PropertyWithImages,
+    },
+    utils::database::PaginatedRecord,
+};
+
+#[derive(Debug, Serialize)]
+pub struct Site {
+    pub tenant_id: i32,
+    pub hero: Hero,
+    pub config: Config,
+    pub social_media: SocialMedia,
+    pub feedback: PaginatedRecord<Feedback>,
+    pub properties: PaginatedRecord<PropertyWithImages>,
+}
@@ -0,0 +1,147 @@
+use std::{fmt::Write, sync::Arc};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::{try_join, TryStreamExt};
+
+use crate::{
+    error::ApiError,
+    modules::{
+        front::{
+            landing::{config, feedback, hero},
+            social_media,
+        },
+        property,
+    },
+    utils::{database::Pagination, xml::escape},
+};
+
+use super::Site;
+
+const FEEDBACK_PAGE_SIZE: u32 = 10;
+const FEATURED_PROPERTIES: u32 = 6;
+
+pub struct Service {
+    hero_service: Arc<hero::Service>,
+    config_service: Arc<config::Service>,
+    social_service: Arc<social_media::Service>,
+    feedback_service: Arc<feedback::Service>,
+    property_service: Arc<property::Service>,
+    site_url_template: Option<String>,
+}
+
+impl Service {
+    pub fn new(
+        hero_service: Arc<hero::Service>,
+        config_service: Arc<config::Service>,
+        social_service: Arc<social_media::Service>,
+        feedback_service: Arc<feedback::Service>,
+        property_service: Arc<property::Service>,
+        site_url_template: Option<String>,
+    ) -> Self {
+        Self {
+            hero_service,
+            config_service,
+            social_service,
+            feedback_service,
+            property_service,
+            site_url_template,
+        }
+    }
+
+    /// Loads everything the landing needs to render in a single round trip.
+    pub async fn find_tenant_site(&self, tenant_id: i32) -> Result<Site, ApiError> {
+        let (hero, config, social_media, feedback, properties) = try_join!(
+            self.hero_service.find_tenant_hero(tenant_id),
+            self.config_service.find_tenant_config(tenant_id),
+            self.social_service.find(tenant_id),
+            self.feedback_service.find_tenant_feedback(
+                tenant_id,
+                Pagination {
+                    page: 1,
+                    per_page: FEEDBACK_PAGE_SIZE,
+                },
+            ),
+            self.property_service.find_all_tenant_properties(
+                tenant_id,
+                Pagination {
+                    page: 1,
+                    per_page: FEATURED_PROPERTIES,
+                },
+            ),
+        )?;
+
+        Ok(Site {
+            tenant_id,
+            hero,
+            config,
+            social_media,
+            feedback,
+            properties,
+        })
+    }
+
+    /// `sitemap.xml` listing the landing and every published listing.
+    pub async fn find_tenant_sitemap(&self, tenant_id: i32) -> Result<String, ApiError> {
+        let site_url = self.site_url(tenant_id)?;
+        let (hero, config, properties) = try_join!(
+            self.hero_service.find_tenant_hero(tenant_id),
+            self.config_service.find_tenant_config(tenant_id),
+            self.property_service
+                .export_tenant_properties(tenant_id)
+                .try_collect::<Vec<_>>(),
+        )?;
+
+        let landing_lastmod = [hero.updated_at, config.updated_at]
+            .into_iter()
+            .chain(properties.iter().map(|p| p.property.updated_at))
+            .flatten()
+            .max();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+        push_sitemap_url(&mut xml, &format!("{}/", site_url), landing_lastmod);
+        for property in &properties {
+            if let Some(slug) = &property.property.slug {
+                push_sitemap_url(
+                    &mut xml,
+                    &format!("{}/properties/{}", site_url, slug),
+                    property.property.updated_at,
+                );
+            }
+        }
+        xml.push_str("</urlset>\n");
+
+        Ok(xml)
+    }
+
+    pub fn find_tenant_robots(&self, tenant_id: i32) -> Result<String, ApiError> {
+        Ok(format!(
+            "User-agent: *\nAllow: /\n\nSitemap: {}/sitemap.xml\n",
+            self.site_url(tenant_id)?
+        ))
+    }
+
+    fn site_url(&self, tenant_id: i32) -> Result<String, ApiError> {
+        let template = self.site_url_template.as_ref().ok_or_else(|| {
+            ApiError::ServiceUnavailable("SITE_URL_TEMPLATE is not configured".to_string())
+        })?;
+
+        Ok(template
+            .replace("{tenant_id}", &tenant_id.to_string())
+            .trim_end_matches('/')
+            .to_string())
+    }
+}
+
+fn push_sitemap_url(xml: &mut String, loc: &str, lastmod: Option<DateTime<Utc>>) {
+    xml.push_str("  <url>\n");
+    let _ = writeln!(xml, "    <loc>{}</loc>", escape(loc));
+    if let Some(lastmod) = lastmod {
+        let _ = writeln!(
+            xml,
+            "    <lastmod>{}</lastmod>",
+            lastmod.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+    }
+    xml.push_str("  </url>\n");
+}
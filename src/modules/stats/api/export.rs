@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{NaiveDate, SecondsFormat};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::{
+    error::ApiError,
+    modules::{
+        stats::{DailyEventCount, Service, Stats, StatsExportKind},
+        tenant,
+    },
+    utils::{
+        export::{export_response, CsvRecord, ExportFormat},
+        lucia,
+    },
+};
+
+use super::handler::PeriodRequest;
+
+impl CsvRecord for DailyEventCount {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "day",
+            "event_type",
+            "property_id",
+            "events",
+            "unique_visitors",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.day.to_string(),
+            self.event_type.as_str().to_string(),
+            self.property_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            self.events.to_string(),
+            self.unique_visitors.to_string(),
+        ]
+    }
+}
+
+/// The most used `details` get their own column; the rest stay in `details` as JSON.
+impl CsvRecord for Stats {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "id",
+            "created_at",
+            "event_type",
+            "property_id",
+            "visitor_id",
+            "device_type",
+            "referrer",
+            "source",
+            "medium",
+            "campaign",
+            "bot",
+            "details",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        let text = |path: &[&str]| -> String {
+            let mut value = self.details.as_ref();
+            for key in path {
+                value = value.and_then(|value| value.get(key));
+            }
+            match value {
+                Some(serde_json::Value::String(text)) => text.clone(),
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            }
+        };
+
+        vec![
+            self.id.to_string(),
+            self.created_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.event_type.as_str().to_string(),
+            text(&["property_id"]),
+            self.visitor_id.clone().unwrap_or_default(),
+            text(&["metadata", "device_type"]),
+            text(&["metadata", "referrer"]),
+            text(&["metadata", "attribution", "source"]),
+            text(&["metadata", "attribution", "medium"]),
+            text(&["metadata", "attribution", "campaign"]),
+            text(&["metadata", "bot"]),
+            self.details
+                .as_ref()
+                .map(|details| details.to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ExportStatsRequest {
+    #[serde(default)]
+    pub kind: StatsExportKind,
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+pub async fn export_stats(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<ExportStatsRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let filename = format!("stats-{}-{}-{}", query.kind.as_str(), query.from, query.to);
+    let period = PeriodRequest {
+        tz: query.tz,
+        from: query.from,
+        to: query.to,
+        include_bots: query.include_bots,
+    }
+    .into_query(tenant.id);
+
+    let response = match query.kind {
+        StatsExportKind::Raw => export_response(
+            service.export_events(period).await?,
+            query.format,
+            &filename,
+        ),
+        StatsExportKind::Daily => export_response(
+            service.export_daily_counts(period).await?,
+            query.format,
+            &filename,
+        ),
+    };
+    Ok(response)
+}
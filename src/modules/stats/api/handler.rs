@@ -1,13 +1,24 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use serde::Deserialize;
 use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
     error::ApiError,
-    modules::stats::{EventType, Service, Stats},
-    utils::lucia,
+    modules::{
+        stats::{
+            AttributionGrouping, Bucket, EventType, FunnelQuery, FunnelUnit, Metadata, PeriodQuery,
+            SeriesQuery, Service, VisitEvent, Visitor,
+        },
+        tenant,
+    },
+    utils::{client_ip::ClientIp, lucia, user_agent, validation::validate_page_url},
 };
 
+/// First-party cookie holding the visitor id on sites served from the API's domain.
+const VISITOR_COOKIE: &str = "vendy_vid";
+
 #[derive(Deserialize)]
 pub struct CreateStats {
     pub event_type: EventType,
@@ -17,6 +28,7 @@ pub struct CreateStats {
 
 pub async fn create_stats(
     service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
     lucia_service: web::Data<Arc<lucia::Service>>,
     req: web::Json<CreateStats>,
     req_headers: HttpRequest,
@@ -31,34 +43,447 @@ pub async fn create_stats(
             "Missing Authorization header".into(),
         ));
     }
-    let _session = lucia_service
+    let session = lucia_service
         .get_session(basic_auth_header.unwrap())
         .await?;
 
-    let stats = Stats {
-        id: 0, // This will be set by the database
-        event_type: req.event_type.clone(),
-        tenant_id: req.tenant_id,
-        details: req.details.clone(),
-        created_at: chrono::Utc::now(),
-    };
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+    if tenant.id != req.tenant_id {
+        return Err(ApiError::Forbidden(
+            "Stats can only be recorded for your own tenant".into(),
+        ));
+    }
+
+    let CreateStats {
+        event_type,
+        tenant_id,
+        details,
+    } = req.into_inner();
+    let metadata = details
+        .as_ref()
+        .and_then(|details| details.get("metadata"))
+        .map(|metadata| serde_json::from_value::<Metadata>(metadata.clone()))
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid metadata: {}", e)))?
+        .unwrap_or_default();
+    let event = VisitEvent::from_details(event_type, details)?;
 
-    let created_stats = service.create(stats).await?;
+    let created_stats = service.create(tenant_id, event, metadata).await?;
     Ok(HttpResponse::Created().json(created_stats))
 }
 
+#[derive(Deserialize, Validate)]
+pub struct VisitRequest {
+    pub tenant_id: i32,
+    #[serde(flatten)]
+    pub event: VisitEvent,
+    /// Id the site keeps in its own first-party cookie.
+    #[validate(length(min = 1, max = 128))]
+    pub visitor_id: Option<String>,
+    /// `location.href` of the page, whose `utm_*` parameters attribute the
+    /// visit. Browsers strip the query string from the `Referer` header of
+    /// requests to other origins, so it has to be sent.
+    #[validate(length(max = 2048), custom(function = "validate_page_url"))]
+    pub url: Option<String>,
+    /// `document.referrer` of the page.
+    #[validate(length(max = 2048), custom(function = "validate_page_url"))]
+    pub referrer: Option<String>,
+}
+
+/// Public endpoint hit by landing pages; everything but the event itself is
+/// read from the request.
+pub async fn record_visit(
+    service: web::Data<Arc<Service>>,
+    client_ip: web::Data<Arc<ClientIp>>,
+    web::Json(visit): web::Json<VisitRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    visit.validate()?;
+
+    let header_value = |name: header::HeaderName| {
+        req_headers
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let user_agent = header_value(header::USER_AGENT);
+    let page = header_value(header::REFERER);
+    let visitor = Visitor {
+        id: visit.visitor_id.or_else(|| {
+            req_headers
+                .cookie(VISITOR_COOKIE)
+                .map(|cookie| cookie.value().to_string())
+                .filter(|id| !id.is_empty() && id.len() <= 128)
+        }),
+        ip_address: client_ip.resolve(&req_headers),
+        user_agent: user_agent.clone(),
+        origin: header_value(header::ORIGIN).or_else(|| page.clone()),
+        page_url: visit
+            .url
+            .filter(|url| !url.is_empty())
+            .or_else(|| page.clone()),
+    };
+    // Sites not sending `referrer` yet still get the page they were called from.
+    let metadata = Metadata {
+        referrer: match visit.referrer {
+            Some(referrer) if referrer.is_empty() => None,
+            Some(referrer) => Some(referrer),
+            None => page,
+        },
+        device_type: user_agent
+            .as_deref()
+            .map(|user_agent| user_agent::device_type(user_agent).to_string()),
+        ip_address: None,
+        user_agent,
+        bot: None,
+        attribution: None,
+    };
+
+    service
+        .record_visit(visit.tenant_id, visit.event, visitor, metadata)
+        .await?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[derive(Deserialize, Validate)]
+pub struct TimeSeriesQuery {
+    pub event_type: EventType,
+    pub property_id: Option<i32>,
+    pub bucket: Option<Bucket>,
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+pub async fn get_visits_time_series(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<TimeSeriesQuery>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let series = service
+        .get_visits_time_series(SeriesQuery {
+            tenant_id: tenant.id,
+            event_type: query.event_type,
+            property_id: query.property_id,
+            bucket: query.bucket.unwrap_or(Bucket::Day),
+            timezone: query.tz.unwrap_or_else(|| "UTC".into()),
+            from: start_of_day(query.from),
+            to: start_of_day(query.to + Duration::days(1)),
+            include_bots: query.include_bots,
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(series))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct PeriodRequest {
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+impl PeriodRequest {
+    pub(super) fn into_query(self, tenant_id: i32) -> PeriodQuery {
+        PeriodQuery {
+            tenant_id,
+            timezone: self.tz.unwrap_or_else(|| "UTC".into()),
+            from: start_of_day(self.from),
+            to: start_of_day(self.to + Duration::days(1)),
+            include_bots: self.include_bots,
+        }
+    }
+}
+
+pub async fn get_visitor_summary(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<PeriodRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let summary = service
+        .get_visitor_summary(query.into_query(tenant.id))
+        .await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+pub async fn get_events_overview(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<PeriodRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let overview = service
+        .get_events_overview(query.into_query(tenant.id))
+        .await?;
+    Ok(HttpResponse::Ok().json(overview))
+}
+
+pub async fn get_event_type_report(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    event_type: web::Path<EventType>,
+    web::Query(query): web::Query<PeriodRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let report = service
+        .get_event_type_report(query.into_query(tenant.id), *event_type)
+        .await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AttributionRequest {
+    pub group_by: Option<AttributionGrouping>,
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+pub async fn get_attribution_report(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<AttributionRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let period = PeriodRequest {
+        tz: query.tz,
+        from: query.from,
+        to: query.to,
+        include_bots: query.include_bots,
+    };
+    let report = service
+        .get_attribution_report(
+            period.into_query(tenant.id),
+            query.group_by.unwrap_or(AttributionGrouping::Source),
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct PropertyAnalyticsRequest {
+    /// Buckets of the views series; defaults to days.
+    pub bucket: Option<Bucket>,
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+pub async fn get_property_analytics(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    property_id: web::Path<i32>,
+    web::Query(query): web::Query<PropertyAnalyticsRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let period = PeriodRequest {
+        tz: query.tz,
+        from: query.from,
+        to: query.to,
+        include_bots: query.include_bots,
+    };
+    let analytics = service
+        .get_property_analytics(
+            period.into_query(tenant.id),
+            *property_id,
+            query.bucket.unwrap_or(Bucket::Day),
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(analytics))
+}
+
+/// Conversions are only followed within a day, as visitor ids change daily.
+const MAX_FUNNEL_WINDOW_MINS: i32 = 24 * 60;
+
+#[derive(Deserialize, Validate)]
+pub struct FunnelRequest {
+    /// Comma-separated event types, in funnel order.
+    #[validate(length(min = 1, max = 200))]
+    pub steps: String,
+    pub unit: Option<FunnelUnit>,
+    /// Time allowed from the first step to the last; defaults to a day.
+    #[validate(range(min = 1, max = "MAX_FUNNEL_WINDOW_MINS"))]
+    pub window_mins: Option<i32>,
+    pub property_id: Option<i32>,
+    /// IANA name such as `America/Lima`; defaults to UTC.
+    #[validate(length(min = 1, max = 64))]
+    pub tz: Option<String>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+pub async fn get_funnel(
+    service: web::Data<Arc<Service>>,
+    tenant_service: web::Data<Arc<tenant::Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    web::Query(query): web::Query<FunnelRequest>,
+    req_headers: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+
+    let steps = query
+        .steps
+        .split(',')
+        .map(|step| {
+            serde_json::from_value::<EventType>(serde_json::Value::String(step.trim().into()))
+                .map_err(|_| ApiError::BadRequest(format!("Unknown event type: {}", step)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = tenant_service.find_by_user_id(&session.user_id).await?;
+
+    let period = PeriodRequest {
+        tz: query.tz,
+        from: query.from,
+        to: query.to,
+        include_bots: query.include_bots,
+    };
+    let funnel = service
+        .get_funnel(FunnelQuery {
+            period: period.into_query(tenant.id),
+            steps,
+            unit: query.unit.unwrap_or(FunnelUnit::Visitor),
+            window_mins: query.window_mins.unwrap_or(MAX_FUNNEL_WINDOW_MINS),
+            property_id: query.property_id,
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(funnel))
+}
+
+fn start_of_day(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default()
+}
+
+/// Reports leave out events classified as bot traffic unless asked not to.
+#[derive(Deserialize)]
+pub struct ReportQuery {
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
 pub async fn get_property_visited_info(
     service: web::Data<Arc<Service>>,
     tenant_id: web::Path<i32>,
+    web::Query(query): web::Query<ReportQuery>,
 ) -> Result<HttpResponse, ApiError> {
-    let property_visited_info = service.get_property_visited_info(*tenant_id).await?;
+    let property_visited_info = service
+        .get_property_visited_info(*tenant_id, query.include_bots)
+        .await?;
     Ok(HttpResponse::Ok().json(property_visited_info))
 }
 
 pub async fn get_landing_visited_info(
     service: web::Data<Arc<Service>>,
     tenant_id: web::Path<i32>,
+    web::Query(query): web::Query<ReportQuery>,
 ) -> Result<HttpResponse, ApiError> {
-    let landing_visited_info = service.get_landing_visited_info(*tenant_id).await?;
+    let landing_visited_info = service
+        .get_landing_visited_info(*tenant_id, query.include_bots)
+        .await?;
     Ok(HttpResponse::Ok().json(landing_visited_info))
 }
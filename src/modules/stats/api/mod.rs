@@ -1,12 +1,27 @@
+mod export;
 mod handler;
 
 use actix_web::web;
-use handler::{create_stats, get_landing_visited_info, get_property_visited_info};
+use export::export_stats;
+use handler::{
+    create_stats, get_attribution_report, get_event_type_report, get_events_overview, get_funnel,
+    get_landing_visited_info, get_property_analytics, get_property_visited_info,
+    get_visitor_summary, get_visits_time_series, record_visit,
+};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/stats")
             .route("", web::post().to(create_stats))
+            .route("/events", web::post().to(record_visit))
+            .route("/events", web::get().to(get_events_overview))
+            .route("/events/{event_type}", web::get().to(get_event_type_report))
+            .route("/timeseries", web::get().to(get_visits_time_series))
+            .route("/visitors", web::get().to(get_visitor_summary))
+            .route("/funnel", web::get().to(get_funnel))
+            .route("/attribution", web::get().to(get_attribution_report))
+            .route("/properties/{id}", web::get().to(get_property_analytics))
+            .route("/export", web::get().to(export_stats))
             .service(
                 web::scope("/tenants/{tenant_id}")
                     .route(
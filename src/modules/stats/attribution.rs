@@ -0,0 +1,271 @@
+use url::Url;
+
+use super::Attribution;
+
+/// Mediums of the sources recognised from the referrer's host.
+const ORGANIC: &str = "organic";
+const SOCIAL: &str = "social";
+const EMAIL: &str = "email";
+const REFERRAL: &str = "referral";
+/// A page of the same site; the visit keeps the source it arrived with.
+pub const INTERNAL: &str = "internal";
+
+/// Hosts, without `www.`, of the search engines, social networks and webmail
+/// visitors commonly arrive from, matched on their registrable name.
+const KNOWN_SOURCES: &[(&str, &str, &str)] = &[
+    ("google", "google", ORGANIC),
+    ("bing", "bing", ORGANIC),
+    ("yahoo", "yahoo", ORGANIC),
+    ("duckduckgo", "duckduckgo", ORGANIC),
+    ("yandex", "yandex", ORGANIC),
+    ("baidu", "baidu", ORGANIC),
+    ("ecosia", "ecosia", ORGANIC),
+    ("facebook", "facebook", SOCIAL),
+    ("fb", "facebook", SOCIAL),
+    ("instagram", "instagram", SOCIAL),
+    ("t", "x", SOCIAL),
+    ("twitter", "x", SOCIAL),
+    ("x", "x", SOCIAL),
+    ("linkedin", "linkedin", SOCIAL),
+    ("lnkd", "linkedin", SOCIAL),
+    ("tiktok", "tiktok", SOCIAL),
+    ("youtube", "youtube", SOCIAL),
+    ("pinterest", "pinterest", SOCIAL),
+    ("whatsapp", "whatsapp", SOCIAL),
+    ("wa", "whatsapp", SOCIAL),
+    ("outlook", "outlook", EMAIL),
+    ("live", "outlook", EMAIL),
+];
+
+/// Second-level labels of country code suffixes such as `com.pe` or `co.uk`.
+const SECOND_LEVEL_LABELS: &[&str] = &["com", "co", "org", "net", "gob", "gov"];
+
+/// Longest source, medium or campaign stored, as in `stats_daily_sources`.
+const MAX_VALUE_LENGTH: usize = 100;
+
+/// Where a visit comes from: the `utm_*` parameters of the page URL when
+/// present, else the click ids ad networks append, else the referrer.
+pub fn attribute(page_url: Option<&str>, referrer: Option<&str>) -> Attribution {
+    let page_url = page_url.and_then(|url| Url::parse(url).ok());
+
+    if let Some(page_url) = &page_url {
+        let param = |name: &str| {
+            page_url
+                .query_pairs()
+                .find(|(key, value)| key == name && !value.trim().is_empty())
+                .map(|(_, value)| normalize(&value))
+        };
+
+        if let Some(source) = param("utm_source") {
+            return Attribution {
+                source,
+                medium: param("utm_medium").unwrap_or_else(|| "(none)".into()),
+                campaign: param("utm_campaign"),
+            };
+        }
+        if param("gclid").is_some() {
+            return Attribution::new("google", "cpc");
+        }
+        if param("fbclid").is_some() {
+            return Attribution::new("facebook", SOCIAL);
+        }
+    }
+
+    let Some(referrer_host) = referrer
+        .and_then(|referrer| Url::parse(referrer).ok())
+        .and_then(|referrer| referrer.host_str().map(host_name))
+    else {
+        return Attribution::direct();
+    };
+
+    let page_host = page_url
+        .as_ref()
+        .and_then(|page_url| page_url.host_str().map(host_name));
+    if page_host.as_deref() == Some(referrer_host.as_str()) {
+        return Attribution::new(&referrer_host, INTERNAL);
+    }
+
+    if referrer_host == "mail.google.com" {
+        return Attribution::new("gmail", EMAIL);
+    }
+    let name = registrable_name(&referrer_host);
+    match KNOWN_SOURCES.iter().find(|(known, _, _)| *known == name) {
+        Some((_, source, medium)) => Attribution::new(source, medium),
+        None => Attribution::new(&referrer_host, REFERRAL),
+    }
+}
+
+impl Attribution {
+    /// A visit typed in, bookmarked or from an app that sends no referrer.
+    pub fn direct() -> Self {
+        Self::new("direct", "(none)")
+    }
+
+    fn new(source: &str, medium: &str) -> Self {
+        Self {
+            source: normalize(source),
+            medium: normalize(medium),
+            campaign: None,
+        }
+    }
+}
+
+fn host_name(host: &str) -> String {
+    let host = host.to_ascii_lowercase();
+    host.strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(host)
+}
+
+/// Label left of the public suffix, e.g. `google` for `news.google.com.pe`.
+fn registrable_name(host: &str) -> &str {
+    let labels: Vec<&str> = host.split('.').collect();
+    let suffix_len = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SECOND_LEVEL_LABELS.contains(second) => 2,
+        _ => 1,
+    };
+
+    labels
+        .len()
+        .checked_sub(suffix_len + 1)
+        .and_then(|index| labels.get(index))
+        .copied()
+        .unwrap_or(host)
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .trim()
+        .to_lowercase()
+        .chars()
+        .take(MAX_VALUE_LENGTH)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribution(source: &str, medium: &str, campaign: Option<&str>) -> Attribution {
+        Attribution {
+            source: source.to_string(),
+            medium: medium.to_string(),
+            campaign: campaign.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn utm_parameters_take_precedence_over_click_ids_and_referrer() {
+        let page = "https://site.com/?utm_source=Newsletter&utm_medium=Email&utm_campaign=Summer_Sale&gclid=abc&fbclid=def";
+
+        assert_eq!(
+            attribute(Some(page), Some("https://www.google.com/")),
+            attribution("newsletter", "email", Some("summer_sale"))
+        );
+    }
+
+    #[test]
+    fn utm_source_without_medium_has_none() {
+        let page = "https://site.com/?utm_source=flyer";
+
+        assert_eq!(
+            attribute(Some(page), None),
+            attribution("flyer", "(none)", None)
+        );
+    }
+
+    #[test]
+    fn blank_utm_source_is_ignored() {
+        let page = "https://site.com/?utm_source=%20&utm_medium=cpc";
+
+        assert_eq!(attribute(Some(page), None), Attribution::direct());
+    }
+
+    #[test]
+    fn click_ids_attribute_paid_traffic() {
+        assert_eq!(
+            attribute(Some("https://site.com/?gclid=abc&fbclid=def"), None),
+            attribution("google", "cpc", None)
+        );
+        assert_eq!(
+            attribute(Some("https://site.com/?fbclid=def"), None),
+            attribution("facebook", "social", None)
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_referrer_is_direct() {
+        assert_eq!(
+            attribute(Some("https://site.com/"), None),
+            Attribution::direct()
+        );
+        assert_eq!(
+            attribute(Some("https://site.com/"), Some("not a url")),
+            Attribution::direct()
+        );
+        assert_eq!(attribute(None, None), Attribution::direct());
+    }
+
+    #[test]
+    fn navigation_within_the_site_is_internal() {
+        assert_eq!(
+            attribute(
+                Some("https://www.site.com/properties/1"),
+                Some("https://site.com/")
+            ),
+            attribution("site.com", INTERNAL, None)
+        );
+    }
+
+    #[test]
+    fn known_hosts_are_matched_on_their_registrable_name() {
+        let from = |referrer: &str| attribute(Some("https://site.com/"), Some(referrer));
+
+        assert_eq!(
+            from("https://www.google.com.pe/"),
+            attribution("google", "organic", None)
+        );
+        assert_eq!(
+            from("https://news.google.com/"),
+            attribution("google", "organic", None)
+        );
+        assert_eq!(from("https://t.co/xyz"), attribution("x", "social", None));
+        assert_eq!(
+            from("https://l.facebook.com/l.php"),
+            attribution("facebook", "social", None)
+        );
+        assert_eq!(
+            from("https://mail.google.com/mail/u/0/"),
+            attribution("gmail", "email", None)
+        );
+    }
+
+    #[test]
+    fn unknown_hosts_are_referrals() {
+        assert_eq!(
+            attribute(
+                Some("https://site.com/"),
+                Some("https://www.Portal-Inmobiliario.pe/listing/1")
+            ),
+            attribution("portal-inmobiliario.pe", "referral", None)
+        );
+    }
+
+    #[test]
+    fn registrable_name_skips_country_code_suffixes() {
+        assert_eq!(registrable_name("google.com"), "google");
+        assert_eq!(registrable_name("news.google.com.pe"), "google");
+        assert_eq!(registrable_name("bbc.co.uk"), "bbc");
+        assert_eq!(registrable_name("google.pe"), "google");
+        assert_eq!(registrable_name("localhost"), "localhost");
+        assert_eq!(registrable_name("co.uk"), "co.uk");
+    }
+
+    #[test]
+    fn values_are_lowercased_and_truncated() {
+        let page = format!("https://site.com/?utm_source={}", "A".repeat(150));
+
+        let attribution = attribute(Some(&page), None);
+        assert_eq!(attribution.source, "a".repeat(MAX_VALUE_LENGTH));
+    }
+}
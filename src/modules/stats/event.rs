@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use validator::{Validate, ValidationErrors};
+
+use crate::error::ApiError;
+
+use super::Metadata;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum EventType {
+    PropertyVisited,
+    LandingVisited,
+    GalleryOpened,
+    /// WhatsApp or phone button clicked.
+    ContactClicked,
+    LeadFormSubmitted,
+    SocialLinkClicked,
+    Shared,
+    SearchPerformed,
+    MapOpened,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 9] = [
+        EventType::PropertyVisited,
+        EventType::LandingVisited,
+        EventType::GalleryOpened,
+        EventType::ContactClicked,
+        EventType::LeadFormSubmitted,
+        EventType::SocialLinkClicked,
+        EventType::Shared,
+        EventType::SearchPerformed,
+        EventType::MapOpened,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::PropertyVisited => "property_visited",
+            EventType::LandingVisited => "landing_visited",
+            EventType::GalleryOpened => "gallery_opened",
+            EventType::ContactClicked => "contact_clicked",
+            EventType::LeadFormSubmitted => "lead_form_submitted",
+            EventType::SocialLinkClicked => "social_link_clicked",
+            EventType::Shared => "shared",
+            EventType::SearchPerformed => "search_performed",
+            EventType::MapOpened => "map_opened",
+        }
+    }
+
+    /// Whether events of this type can refer to a listing.
+    pub fn has_property(&self) -> bool {
+        !matches!(
+            self,
+            EventType::LandingVisited | EventType::SocialLinkClicked | EventType::SearchPerformed
+        )
+    }
+
+    /// Path within `details` of the value reports break this type down by.
+    pub fn breakdown_path(&self) -> &'static [&'static str] {
+        match self {
+            EventType::PropertyVisited
+            | EventType::GalleryOpened
+            | EventType::LeadFormSubmitted
+            | EventType::MapOpened => &["property_id"],
+            EventType::LandingVisited => &["metadata", "referrer"],
+            EventType::ContactClicked | EventType::Shared => &["channel"],
+            EventType::SocialLinkClicked => &["network"],
+            EventType::SearchPerformed => &["filters", "city"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PropertyEvent {
+    pub property_id: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct LandingEvent {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct GalleryOpened {
+    pub property_id: i32,
+    /// Position of the image the gallery was opened at.
+    #[validate(range(max = 100))]
+    pub image_index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactChannel {
+    Whatsapp,
+    Phone,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ContactClicked {
+    pub property_id: Option<i32>,
+    pub channel: ContactChannel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct LeadFormSubmitted {
+    pub property_id: Option<i32>,
+}
+
+/// Networks a tenant can link to from its site, as in `social_media_links`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SocialNetwork {
+    Facebook,
+    Instagram,
+    Tiktok,
+    Linkedin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SocialLinkClicked {
+    pub network: SocialNetwork,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareChannel {
+    Whatsapp,
+    Facebook,
+    X,
+    Email,
+    CopyLink,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct Shared {
+    pub property_id: i32,
+    pub channel: ShareChannel,
+}
+
+/// Filters a visitor searched listings with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct SearchFilters {
+    #[validate(length(max = 200))]
+    pub q: Option<String>,
+    #[validate(length(max = 50))]
+    pub property_type: Option<String>,
+    #[validate(length(max = 50))]
+    pub status: Option<String>,
+    #[validate(length(max = 100))]
+    pub city: Option<String>,
+    #[validate(range(min = 0.0))]
+    pub min_price: Option<f64>,
+    #[validate(range(min = 0.0))]
+    pub max_price: Option<f64>,
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    #[validate(range(max = 20))]
+    pub bedrooms: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SearchPerformed {
+    #[serde(default)]
+    #[validate(nested)]
+    pub filters: SearchFilters,
+    /// Listings the search returned.
+    pub results: Option<u32>,
+}
+
+/// An event with its typed payload. The payload becomes the event's
+/// `details`, next to the request `Metadata`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum VisitEvent {
+    PropertyVisited(PropertyEvent),
+    LandingVisited(LandingEvent),
+    GalleryOpened(GalleryOpened),
+    ContactClicked(ContactClicked),
+    LeadFormSubmitted(LeadFormSubmitted),
+    SocialLinkClicked(SocialLinkClicked),
+    Shared(Shared),
+    SearchPerformed(SearchPerformed),
+    MapOpened(PropertyEvent),
+}
+
+impl VisitEvent {
+    /// Reads the payload of an `event_type` event out of loose `details`.
+    pub fn from_details(
+        event_type: EventType,
+        details: Option<JsonValue>,
+    ) -> Result<Self, ApiError> {
+        let mut details = match details {
+            Some(JsonValue::Object(details)) => details,
+            None | Some(JsonValue::Null) => Default::default(),
+            Some(_) => return Err(ApiError::BadRequest("details must be an object".into())),
+        };
+        details.insert("event_type".into(), serde_json::to_value(event_type)?);
+
+        serde_json::from_value(JsonValue::Object(details))
+            .map_err(|e| ApiError::BadRequest(format!("Invalid details: {}", e)))
+    }
+
+    pub fn event_type(&self) -> EventType {
+        match self {
+            VisitEvent::PropertyVisited(_) => EventType::PropertyVisited,
+            VisitEvent::LandingVisited(_) => EventType::LandingVisited,
+            VisitEvent::GalleryOpened(_) => EventType::GalleryOpened,
+            VisitEvent::ContactClicked(_) => EventType::ContactClicked,
+            VisitEvent::LeadFormSubmitted(_) => EventType::LeadFormSubmitted,
+            VisitEvent::SocialLinkClicked(_) => EventType::SocialLinkClicked,
+            VisitEvent::Shared(_) => EventType::Shared,
+            VisitEvent::SearchPerformed(_) => EventType::SearchPerformed,
+            VisitEvent::MapOpened(_) => EventType::MapOpened,
+        }
+    }
+
+    /// Listing the event refers to, if any.
+    pub fn property_id(&self) -> Option<i32> {
+        match self {
+            VisitEvent::PropertyVisited(event) | VisitEvent::MapOpened(event) => {
+                Some(event.property_id)
+            }
+            VisitEvent::GalleryOpened(event) => Some(event.property_id),
+            VisitEvent::ContactClicked(event) => event.property_id,
+            VisitEvent::LeadFormSubmitted(event) => event.property_id,
+            VisitEvent::Shared(event) => Some(event.property_id),
+            VisitEvent::LandingVisited(_)
+            | VisitEvent::SocialLinkClicked(_)
+            | VisitEvent::SearchPerformed(_) => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            VisitEvent::PropertyVisited(event) | VisitEvent::MapOpened(event) => event.validate(),
+            VisitEvent::LandingVisited(event) => event.validate(),
+            VisitEvent::GalleryOpened(event) => event.validate(),
+            VisitEvent::ContactClicked(event) => event.validate(),
+            VisitEvent::LeadFormSubmitted(event) => event.validate(),
+            VisitEvent::SocialLinkClicked(event) => event.validate(),
+            VisitEvent::Shared(event) => event.validate(),
+            VisitEvent::SearchPerformed(event) => event.validate(),
+        }
+    }
+
+    /// The `details` stored for the event: its payload plus `metadata`.
+    pub fn details(&self, metadata: &Metadata) -> Result<JsonValue, ApiError> {
+        let mut details = match self {
+            VisitEvent::PropertyVisited(event) | VisitEvent::MapOpened(event) => {
+                serde_json::to_value(event)?
+            }
+            VisitEvent::LandingVisited(event) => serde_json::to_value(event)?,
+            VisitEvent::GalleryOpened(event) => serde_json::to_value(event)?,
+            VisitEvent::ContactClicked(event) => serde_json::to_value(event)?,
+            VisitEvent::LeadFormSubmitted(event) => serde_json::to_value(event)?,
+            VisitEvent::SocialLinkClicked(event) => serde_json::to_value(event)?,
+            VisitEvent::Shared(event) => serde_json::to_value(event)?,
+            VisitEvent::SearchPerformed(event) => serde_json::to_value(event)?,
+        };
+
+        if let JsonValue::Object(details) = &mut details {
+            details.insert("metadata".into(), serde_json::to_value(metadata)?);
+        }
+        Ok(details)
+    }
+}
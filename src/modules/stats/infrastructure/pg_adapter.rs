@@ -1,34 +1,308 @@
+use std::fmt::Write;
+
 use async_trait::async_trait;
-use chrono::Utc;
-use sqlx::Row;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::stream::BoxStream;
+use sqlx::{PgPool, Row};
 
 use crate::{
     error::ApiError,
-    modules::stats::{port::DBRepository, Info, LandingVisitedInfo, PropertyVisitedInfo, Stats},
-    utils::database::PostgresRepository,
+    modules::stats::{
+        port::DBRepository, Attribution, AttributionCounts, AttributionGrouping, BreakdownEntry,
+        Bucket, DailyEventCount, EventTotals, EventType, FunnelQuery, Info, LandingVisitedInfo,
+        PeriodQuery, PropertyVisitedInfo, SeriesPoint, SeriesQuery, Stats, VisitorCounts,
+    },
+    utils::database::{cursor_stream, PostgresRepository, Value},
 };
 
+/// Rejects time zone names Postgres does not know before they reach `AT TIME ZONE`.
+async fn ensure_timezone(pg_pool: &PgPool, timezone: &str) -> Result<(), ApiError> {
+    let known_timezone: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(timezone)
+            .fetch_one(pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+    if known_timezone {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Unknown timezone: {}",
+            timezone
+        )))
+    }
+}
+
+/// Listing of a raw `stats s` row, `0` when it refers to none.
+const PROPERTY_COLUMN: &str = "COALESCE((s.details->>'property_id')::int, 0)";
+const REFERRER_COLUMN: &str = "COALESCE(LEFT(s.details->'metadata'->>'referrer', 500), '')";
+/// Host of the referrer without `www.`, or `''`.
+const REFERRER_HOST_COLUMN: &str = "COALESCE(LEFT(LOWER(SUBSTRING(
+        s.details->'metadata'->>'referrer' FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://(?:www\\.)?([^/:?#]+)'
+    )), 255), '')";
+
+/// Value a raw `stats s` row's event type is broken down by, or `''`.
+fn dimension_column() -> String {
+    let dimension: Vec<String> = EventType::ALL
+        .iter()
+        .map(|event_type| {
+            format!(
+                "WHEN '{}' THEN s.details #>> '{{{}}}'",
+                event_type.as_str(),
+                event_type.breakdown_path().join(",")
+            )
+        })
+        .collect();
+
+    format!(
+        "COALESCE(LEFT(CASE s.event_type {} END, 255), '')",
+        dimension.join(" ")
+    )
+}
+
+/// A daily rollup table, counting events and visitors per UTC day, tenant,
+/// event type, bot flag and `keys`. Each key comes with its expression for a
+/// raw `stats s` row. Visitors only add up across days, so every breakdown
+/// reporting them needs a table keyed by it alone.
+struct Rollup {
+    table: &'static str,
+    keys: Vec<(&'static str, String)>,
+}
+
+/// Per listing, referrer and dimension, for the events and referrers lists.
+fn daily() -> Rollup {
+    Rollup {
+        table: "stats_daily",
+        keys: vec![
+            ("property_id", PROPERTY_COLUMN.into()),
+            ("referrer", REFERRER_COLUMN.into()),
+            ("dimension", dimension_column()),
+        ],
+    }
+}
+
+fn daily_totals() -> Rollup {
+    Rollup {
+        table: "stats_daily_totals",
+        keys: vec![],
+    }
+}
+
+fn daily_sources() -> Rollup {
+    Rollup {
+        table: "stats_daily_sources",
+        keys: vec![
+            (
+                "source",
+                "COALESCE(LEFT(s.details->'metadata'->'attribution'->>'source', 100), '(not set)')"
+                    .into(),
+            ),
+            (
+                "medium",
+                "COALESCE(LEFT(s.details->'metadata'->'attribution'->>'medium', 100), '(not set)')"
+                    .into(),
+            ),
+            (
+                "campaign",
+                "COALESCE(LEFT(s.details->'metadata'->'attribution'->>'campaign', 100), '')".into(),
+            ),
+        ],
+    }
+}
+
+fn daily_dimensions() -> Rollup {
+    Rollup {
+        table: "stats_daily_dimensions",
+        keys: vec![("dimension", dimension_column())],
+    }
+}
+
+fn daily_properties() -> Rollup {
+    Rollup {
+        table: "stats_daily_properties",
+        keys: vec![("property_id", PROPERTY_COLUMN.into())],
+    }
+}
+
+fn daily_referrers() -> Rollup {
+    Rollup {
+        table: "stats_daily_referrers",
+        keys: vec![
+            ("property_id", PROPERTY_COLUMN.into()),
+            ("referrer_host", REFERRER_HOST_COLUMN.into()),
+        ],
+    }
+}
+
+fn rollups() -> [Rollup; 6] {
+    [
+        daily(),
+        daily_totals(),
+        daily_sources(),
+        daily_dimensions(),
+        daily_properties(),
+        daily_referrers(),
+    ]
+}
+
+/// Raw events not rolled up yet, within the optional `[$2, $3)` local days.
+const RAW_EVENTS_FILTER: &str = "s.tenant_id = $1
+    AND s.created_at >= COALESCE(
+        ((SELECT rolled_up_to FROM rolled_up) + 1)::timestamp AT TIME ZONE 'UTC',
+        '-infinity'::timestamptz
+    )
+    AND ($2::date IS NULL OR s.created_at >= ($2::timestamp AT TIME ZONE $5))
+    AND ($3::date IS NULL OR s.created_at < ($3::timestamp AT TIME ZONE $5))
+    AND ($4 OR s.details->'metadata'->'bot' IS NULL)";
+
+impl Rollup {
+    /// CTE named after the table without `stats_`, with its rows for the
+    /// days rolled up so far and the same counts computed from the raw events
+    /// after them. Binds `$1` tenant_id, `$2` the first day and `$3` the day
+    /// after the last, both optional, `$4` include_bots and `$5` the timezone
+    /// of the days. Rollups hold UTC days, so in any other timezone the
+    /// counts come from the raw events alone.
+    fn cte(&self) -> String {
+        let columns: String = self
+            .keys
+            .iter()
+            .map(|(key, _)| format!("d.{}, ", key))
+            .collect();
+        let expressions: String = self
+            .keys
+            .iter()
+            .map(|(_, expression)| format!("{}, ", expression))
+            .collect();
+        let group_by: Vec<String> = (1..=self.keys.len() + 2).map(|i| i.to_string()).collect();
+
+        format!(
+            "rolled_up AS (SELECT rolled_up_to FROM stats_rollup_state WHERE $5 = 'UTC'),
+             {name} AS (
+                SELECT d.day, d.event_type, {columns}d.events, d.visitors
+                FROM {table} d
+                WHERE d.tenant_id = $1
+                AND d.day <= (SELECT rolled_up_to FROM rolled_up)
+                AND ($2::date IS NULL OR d.day >= $2::date)
+                AND ($3::date IS NULL OR d.day < $3::date)
+                AND ($4 OR NOT d.is_bot)
+                UNION ALL
+                SELECT (s.created_at AT TIME ZONE $5)::date, s.event_type, {expressions}
+                       COUNT(*), COUNT(DISTINCT s.visitor_id)
+                FROM stats s
+                WHERE {filter}
+                GROUP BY {group_by}
+             )",
+            name = self.table.trim_start_matches("stats_"),
+            table = self.table,
+            columns = columns,
+            expressions = expressions,
+            filter = RAW_EVENTS_FILTER,
+            group_by = group_by.join(", ")
+        )
+    }
+
+    /// Inserts the counts of the UTC days `$1` to `$2`, both included.
+    fn insert(&self) -> String {
+        let columns: String = self
+            .keys
+            .iter()
+            .map(|(key, _)| format!("{}, ", key))
+            .collect();
+        let expressions: String = self
+            .keys
+            .iter()
+            .map(|(_, expression)| format!("{}, ", expression))
+            .collect();
+        let group_by: Vec<String> = (1..=self.keys.len() + 4).map(|i| i.to_string()).collect();
+
+        format!(
+            "INSERT INTO {table} (day, tenant_id, event_type, {columns}is_bot, events, visitors)
+             SELECT (s.created_at AT TIME ZONE 'UTC')::date, s.tenant_id, s.event_type, {expressions}
+                    s.details->'metadata'->'bot' IS NOT NULL,
+                    COUNT(*), COUNT(DISTINCT s.visitor_id)
+             FROM stats s
+             WHERE s.created_at >= ($1::timestamp AT TIME ZONE 'UTC')
+             AND s.created_at < (($2 + 1)::timestamp AT TIME ZONE 'UTC')
+             GROUP BY {group_by}",
+            table = self.table,
+            columns = columns,
+            expressions = expressions,
+            group_by = group_by.join(", ")
+        )
+    }
+}
+
+/// Series with daily or longer buckets, read from the rollups.
+async fn daily_series(pg_pool: &PgPool, query: &SeriesQuery) -> Result<Vec<SeriesPoint>, ApiError> {
+    let rows = sqlx::query(&format!(
+        "WITH {},
+         counts AS (
+            SELECT date_trunc($6, day::timestamp) AS bucket_start, SUM(events)::bigint AS visits
+            FROM daily_properties
+            WHERE event_type = $7
+            AND ($8::int IS NULL OR property_id = $8)
+            GROUP BY 1
+         )
+         SELECT b.bucket_start, COALESCE(c.visits, 0) AS visits
+         FROM generate_series(
+            date_trunc($6, $2::timestamp),
+            $3::timestamp - interval '1 day',
+            ('1 ' || $6)::interval
+         ) AS b(bucket_start)
+         LEFT JOIN counts c ON c.bucket_start = b.bucket_start
+         ORDER BY b.bucket_start",
+        daily_properties().cte()
+    ))
+    .bind(query.tenant_id)
+    .bind(query.from.date())
+    .bind(query.to.date())
+    .bind(query.include_bots)
+    .bind(&query.timezone)
+    .bind(query.bucket.as_str())
+    .bind(query.event_type)
+    .bind(query.property_id)
+    .fetch_all(pg_pool)
+    .await
+    .map_err(ApiError::DatabaseError)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SeriesPoint {
+            bucket_start: row.get("bucket_start"),
+            visits: row.get("visits"),
+        })
+        .collect())
+}
+
 #[async_trait]
 impl DBRepository for PostgresRepository {
     async fn create(&self, stats: Stats) -> Result<Stats, ApiError> {
         let row = sqlx::query(
-            "INSERT INTO stats (event_type, tenant_id, details, created_at) 
-             VALUES ($1, $2, $3, $4) 
-             RETURNING id, event_type, tenant_id, details, created_at",
+            "INSERT INTO stats (event_type, tenant_id, details, visitor_id, created_at) 
+             VALUES ($1, $2, $3, $4, $5) 
+             RETURNING id, event_type, tenant_id, details, visitor_id, created_at",
         )
-        .bind(&stats.event_type)
+        .bind(stats.event_type)
         .bind(stats.tenant_id)
         .bind(&stats.details)
+        .bind(&stats.visitor_id)
         .bind(Utc::now())
         .fetch_one(&*self.pg_pool)
         .await
-        .map_err(|e| ApiError::DatabaseError(e))?;
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_error) if db_error.is_foreign_key_violation() => {
+                ApiError::NotFound(format!("Tenant with id {} not found", stats.tenant_id))
+            }
+            _ => ApiError::DatabaseError(e),
+        })?;
 
         Ok(Stats {
             id: row.get("id"),
             event_type: row.get("event_type"),
             tenant_id: row.get("tenant_id"),
             details: row.get("details"),
+            visitor_id: row.get("visitor_id"),
             created_at: row.get("created_at"),
         })
     }
@@ -36,21 +310,27 @@ impl DBRepository for PostgresRepository {
     async fn get_property_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<Vec<PropertyVisitedInfo>, ApiError> {
-        let rows = sqlx::query(
-            "SELECT 
-                (s.details->>'property_id')::int as property_id,
-                COUNT(*) as visit_count,
-                COALESCE(ARRAY_AGG(DISTINCT s.details->'metadata'->>'referrer') FILTER (WHERE s.details->'metadata'->>'referrer' IS NOT NULL), ARRAY[]::TEXT[]) as referrer
-             FROM stats s
-             WHERE s.tenant_id = $1 
-             AND s.event_type = 'property_visited'
-             GROUP BY s.details->>'property_id'"
-        )
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT property_id,
+                    SUM(events)::bigint AS visit_count,
+                    COALESCE(ARRAY_AGG(DISTINCT referrer) FILTER (WHERE referrer <> ''), ARRAY[]::TEXT[]) AS referrer
+             FROM daily
+             WHERE event_type = 'property_visited'
+             AND property_id <> 0
+             GROUP BY property_id",
+            daily().cte()
+        ))
         .bind(tenant_id)
+        .bind(None::<NaiveDate>)
+        .bind(None::<NaiveDate>)
+        .bind(include_bots)
+        .bind("UTC")
         .fetch_all(&*self.pg_pool)
         .await
-        .map_err(|e| ApiError::DatabaseError(e))?;
+        .map_err(ApiError::DatabaseError)?;
 
         if rows.is_empty() {
             return Err(ApiError::NotFound(format!(
@@ -76,19 +356,24 @@ impl DBRepository for PostgresRepository {
     async fn get_landing_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<LandingVisitedInfo, ApiError> {
-        let row = sqlx::query(
-            "SELECT 
-                COUNT(*) as visit_count,
-                COALESCE(ARRAY_AGG(DISTINCT s.details->'metadata'->>'referrer') FILTER (WHERE s.details->'metadata'->>'referrer' IS NOT NULL), ARRAY[]::TEXT[]) as referrer
-             FROM stats s
-             WHERE s.tenant_id = $1 
-             AND s.event_type = 'landing_visited'"
-        )
+        let row = sqlx::query(&format!(
+            "WITH {}
+             SELECT COALESCE(SUM(events), 0)::bigint AS visit_count,
+                    COALESCE(ARRAY_AGG(DISTINCT referrer) FILTER (WHERE referrer <> ''), ARRAY[]::TEXT[]) AS referrer
+             FROM daily
+             WHERE event_type = 'landing_visited'",
+            daily().cte()
+        ))
         .bind(tenant_id)
+        .bind(None::<NaiveDate>)
+        .bind(None::<NaiveDate>)
+        .bind(include_bots)
+        .bind("UTC")
         .fetch_optional(&*self.pg_pool)
         .await
-        .map_err(|e| ApiError::DatabaseError(e))?;
+        .map_err(ApiError::DatabaseError)?;
 
         match row {
             Some(row) => Ok(LandingVisitedInfo {
@@ -104,4 +389,624 @@ impl DBRepository for PostgresRepository {
             ))),
         }
     }
+
+    async fn visits_series(&self, query: &SeriesQuery) -> Result<Vec<SeriesPoint>, ApiError> {
+        if !matches!(query.bucket, Bucket::Hour) {
+            return daily_series(&self.pg_pool, query).await;
+        }
+        ensure_timezone(&self.pg_pool, &query.timezone).await?;
+
+        // Buckets are built in local time, and the bounds are converted back
+        // to instants so the range scan can use the created_at index.
+        let rows = sqlx::query(
+            "WITH counts AS (
+                SELECT date_trunc($1, s.created_at AT TIME ZONE $2) AS bucket_start,
+                       COUNT(*) AS visits
+                FROM stats s
+                WHERE s.tenant_id = $3
+                AND s.event_type = $4
+                AND s.created_at >= ($5::timestamp AT TIME ZONE $2)
+                AND s.created_at < ($6::timestamp AT TIME ZONE $2)
+                AND ($7::int IS NULL OR (s.details->>'property_id')::int = $7)
+                AND ($8 OR s.details->'metadata'->'bot' IS NULL)
+                GROUP BY 1
+             )
+             SELECT b.bucket_start, COALESCE(c.visits, 0) AS visits
+             FROM generate_series(
+                date_trunc($1, $5::timestamp),
+                $6::timestamp - interval '1 microsecond',
+                ('1 ' || $1)::interval
+             ) AS b(bucket_start)
+             LEFT JOIN counts c ON c.bucket_start = b.bucket_start
+             ORDER BY b.bucket_start",
+        )
+        .bind(query.bucket.as_str())
+        .bind(&query.timezone)
+        .bind(query.tenant_id)
+        .bind(query.event_type)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.property_id)
+        .bind(query.include_bots)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SeriesPoint {
+                bucket_start: row.get("bucket_start"),
+                visits: row.get("visits"),
+            })
+            .collect())
+    }
+
+    async fn visitor_counts(
+        &self,
+        query: &PeriodQuery,
+        session_timeout_mins: i32,
+    ) -> Result<VisitorCounts, ApiError> {
+        ensure_timezone(&self.pg_pool, &query.timezone).await?;
+
+        // A visitor's event starts a new session when it is their first one or
+        // comes more than the timeout after the previous one.
+        let row = sqlx::query(
+            "WITH events AS (
+                SELECT s.visitor_id,
+                       s.created_at,
+                       s.created_at - LAG(s.created_at) OVER (
+                           PARTITION BY s.visitor_id ORDER BY s.created_at
+                       ) AS gap
+                FROM stats s
+                WHERE s.tenant_id = $1
+                AND s.created_at >= ($3::timestamp AT TIME ZONE $2)
+                AND s.created_at < ($4::timestamp AT TIME ZONE $2)
+                AND ($6 OR s.details->'metadata'->'bot' IS NULL)
+             ),
+             numbered AS (
+                SELECT visitor_id,
+                       COUNT(*) FILTER (WHERE gap IS NULL OR gap > make_interval(mins => $5))
+                           OVER (PARTITION BY visitor_id ORDER BY created_at) AS session
+                FROM events
+                WHERE visitor_id IS NOT NULL
+             ),
+             sessions AS (
+                SELECT visitor_id, session, COUNT(*) AS page_views
+                FROM numbered
+                GROUP BY visitor_id, session
+             )
+             SELECT
+                (SELECT COUNT(*) FROM events) AS page_views,
+                (SELECT COUNT(DISTINCT visitor_id) FROM sessions) AS unique_visitors,
+                (SELECT COUNT(*) FROM sessions) AS sessions,
+                (SELECT COUNT(*) FROM sessions WHERE page_views = 1) AS bounces,
+                (SELECT COALESCE(SUM(page_views), 0)::bigint FROM sessions) AS session_page_views",
+        )
+        .bind(query.tenant_id)
+        .bind(&query.timezone)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(session_timeout_mins)
+        .bind(query.include_bots)
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(VisitorCounts {
+            page_views: row.get("page_views"),
+            unique_visitors: row.get("unique_visitors"),
+            sessions: row.get("sessions"),
+            bounces: row.get("bounces"),
+            session_page_views: row.get("session_page_views"),
+        })
+    }
+
+    async fn event_totals(&self, query: &PeriodQuery) -> Result<Vec<EventTotals>, ApiError> {
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT event_type,
+                    SUM(events)::bigint AS events,
+                    SUM(visitors)::bigint AS unique_visitors
+             FROM daily_totals
+             GROUP BY event_type",
+            daily_totals().cte()
+        ))
+        .bind(query.tenant_id)
+        .bind(query.from.date())
+        .bind(query.to.date())
+        .bind(query.include_bots)
+        .bind(&query.timezone)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EventTotals {
+                event_type: row.get("event_type"),
+                events: row.get("events"),
+                unique_visitors: row.get("unique_visitors"),
+            })
+            .collect())
+    }
+
+    async fn event_breakdown(
+        &self,
+        query: &PeriodQuery,
+        event_type: EventType,
+        limit: i64,
+    ) -> Result<Vec<BreakdownEntry>, ApiError> {
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT NULLIF(dimension, '') AS key,
+                    SUM(events)::bigint AS events,
+                    SUM(visitors)::bigint AS unique_visitors
+             FROM daily_dimensions
+             WHERE event_type = $6
+             GROUP BY dimension
+             ORDER BY events DESC, key
+             LIMIT $7",
+            daily_dimensions().cte()
+        ))
+        .bind(query.tenant_id)
+        .bind(query.from.date())
+        .bind(query.to.date())
+        .bind(query.include_bots)
+        .bind(&query.timezone)
+        .bind(event_type)
+        .bind(limit)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| BreakdownEntry {
+                key: row.get("key"),
+                events: row.get("events"),
+                unique_visitors: row.get("unique_visitors"),
+            })
+            .collect())
+    }
+
+    async fn property_event_totals(
+        &self,
+        query: &PeriodQuery,
+        property_id: i32,
+    ) -> Result<Vec<EventTotals>, ApiError> {
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT event_type,
+                    SUM(events)::bigint AS events,
+                    SUM(visitors)::bigint AS unique_visitors
+             FROM daily_properties
+             WHERE property_id = $6
+             GROUP BY event_type",
+            daily_properties().cte()
+        ))
+        .bind(query.tenant_id)
+        .bind(query.from.date())
+        .bind(query.to.date())
+        .bind(query.include_bots)
+        .bind(&query.timezone)
+        .bind(property_id)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EventTotals {
+                event_type: row.get("event_type"),
+                events: row.get("events"),
+                unique_visitors: row.get("unique_visitors"),
+            })
+            .collect())
+    }
+
+    async fn property_referrers(
+        &self,
+        query: &PeriodQuery,
+        property_id: i32,
+        limit: i64,
+    ) -> Result<Vec<BreakdownEntry>, ApiError> {
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT NULLIF(referrer_host, '') AS key,
+                    SUM(events)::bigint AS events,
+                    SUM(visitors)::bigint AS unique_visitors
+             FROM daily_referrers
+             WHERE event_type = $6
+             AND property_id = $7
+             GROUP BY referrer_host
+             ORDER BY events DESC, key
+             LIMIT $8",
+            daily_referrers().cte()
+        ))
+        .bind(query.tenant_id)
+        .bind(query.from.date())
+        .bind(query.to.date())
+        .bind(query.include_bots)
+        .bind(&query.timezone)
+        .bind(EventType::PropertyVisited)
+        .bind(property_id)
+        .bind(limit)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| BreakdownEntry {
+                key: row.get("key"),
+                events: row.get("events"),
+                unique_visitors: row.get("unique_visitors"),
+            })
+            .collect())
+    }
+
+    fn stream_daily_counts(
+        &self,
+        query: &PeriodQuery,
+    ) -> BoxStream<'static, Result<DailyEventCount, ApiError>> {
+        let sql = format!(
+            "WITH {}
+             SELECT day, event_type, NULLIF(property_id, 0) AS property_id,
+                    SUM(events)::bigint AS events,
+                    SUM(visitors)::bigint AS unique_visitors
+             FROM daily_properties
+             GROUP BY day, event_type, property_id
+             ORDER BY day, event_type, property_id",
+            daily_properties().cte()
+        );
+        // Cursor arguments have no date type, hence the casts in the CTE.
+        let args = vec![
+            Value::Int(query.tenant_id.into()),
+            Value::String(query.from.date().to_string()),
+            Value::String(query.to.date().to_string()),
+            Value::Bool(query.include_bots),
+            Value::String(query.timezone.clone()),
+        ];
+
+        cursor_stream(self.pg_pool.clone(), sql, args, |row| {
+            Ok(DailyEventCount {
+                day: row.get("day"),
+                event_type: row.get("event_type"),
+                property_id: row.get("property_id"),
+                events: row.get("events"),
+                unique_visitors: row.get("unique_visitors"),
+            })
+        })
+    }
+
+    async fn check_timezone(&self, timezone: &str) -> Result<(), ApiError> {
+        ensure_timezone(&self.pg_pool, timezone).await
+    }
+
+    fn stream_events(&self, query: &PeriodQuery) -> BoxStream<'static, Result<Stats, ApiError>> {
+        let sql = "SELECT id, event_type, tenant_id, details, visitor_id, created_at
+             FROM stats s
+             WHERE s.tenant_id = $1
+             AND s.created_at >= ($2::timestamp AT TIME ZONE $3)
+             AND s.created_at < ($4::timestamp AT TIME ZONE $3)
+             AND ($5 OR s.details->'metadata'->'bot' IS NULL)
+             ORDER BY s.created_at, s.id"
+            .to_string();
+        let args = vec![
+            Value::Int(query.tenant_id.into()),
+            Value::String(query.from.to_string()),
+            Value::String(query.timezone.clone()),
+            Value::String(query.to.to_string()),
+            Value::Bool(query.include_bots),
+        ];
+
+        cursor_stream(self.pg_pool.clone(), sql, args, |row| {
+            Ok(Stats {
+                id: row.get("id"),
+                event_type: row.get("event_type"),
+                tenant_id: row.get("tenant_id"),
+                details: row.get("details"),
+                visitor_id: row.get("visitor_id"),
+                created_at: row.get("created_at"),
+            })
+        })
+    }
+
+    async fn attribution_counts(
+        &self,
+        query: &PeriodQuery,
+        group_by: AttributionGrouping,
+        limit: i64,
+    ) -> Result<Vec<AttributionCounts>, ApiError> {
+        let (campaign, grouping) = match group_by {
+            AttributionGrouping::Source => ("NULL::text", "source, medium"),
+            AttributionGrouping::Campaign => ("campaign", "source, medium, campaign"),
+        };
+        let visit_types =
+            [EventType::LandingVisited, EventType::PropertyVisited].map(|t| t.as_str());
+
+        let rows = sqlx::query(&format!(
+            "WITH {}
+             SELECT source, medium, {} AS campaign,
+                    COALESCE(SUM(events) FILTER (WHERE event_type = ANY($6::text[])), 0)::bigint AS visits,
+                    COALESCE(SUM(events) FILTER (WHERE event_type = $7), 0)::bigint AS contacts,
+                    COALESCE(SUM(events) FILTER (WHERE event_type = $8), 0)::bigint AS leads
+             FROM daily_sources
+             WHERE (event_type = ANY($6::text[]) OR event_type IN ($7, $8))
+             AND ($9 OR campaign <> '')
+             GROUP BY {}
+             ORDER BY visits DESC, leads DESC, {}
+             LIMIT $10",
+            daily_sources().cte(),
+            campaign,
+            grouping,
+            grouping
+        ))
+        .bind(query.tenant_id)
+        .bind(query.from.date())
+        .bind(query.to.date())
+        .bind(query.include_bots)
+        .bind(&query.timezone)
+        .bind(&visit_types[..])
+        .bind(EventType::ContactClicked)
+        .bind(EventType::LeadFormSubmitted)
+        .bind(matches!(group_by, AttributionGrouping::Source))
+        .bind(limit)
+        .fetch_all(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AttributionCounts {
+                source: row.get("source"),
+                medium: row.get("medium"),
+                campaign: row.get("campaign"),
+                visits: row.get("visits"),
+                contacts: row.get("contacts"),
+                leads: row.get("leads"),
+            })
+            .collect())
+    }
+
+    async fn last_attribution(
+        &self,
+        tenant_id: i32,
+        visitor_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<Attribution>, ApiError> {
+        let attribution: Option<serde_json::Value> = sqlx::query_scalar(
+            "SELECT s.details->'metadata'->'attribution'
+             FROM stats s
+             WHERE s.tenant_id = $1
+             AND s.visitor_id = $2
+             AND s.created_at >= $3
+             AND s.details->'metadata'->'attribution' IS NOT NULL
+             ORDER BY s.created_at DESC
+             LIMIT 1",
+        )
+        .bind(tenant_id)
+        .bind(visitor_id)
+        .bind(since)
+        .fetch_optional(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(attribution.and_then(|attribution| serde_json::from_value(attribution).ok()))
+    }
+
+    async fn funnel_counts(
+        &self,
+        query: &FunnelQuery,
+        session_timeout_mins: i32,
+    ) -> Result<Vec<i64>, ApiError> {
+        let period = &query.period;
+        ensure_timezone(&self.pg_pool, &period.timezone).await?;
+
+        let steps: Vec<&str> = query.steps.iter().map(EventType::as_str).collect();
+        let property_types: Vec<&str> = EventType::ALL
+            .iter()
+            .filter(|event_type| event_type.has_property())
+            .map(EventType::as_str)
+            .collect();
+
+        // Sessions are cut over all of a visitor's events, not only the
+        // funnel's, before the events of the steps are picked out.
+        let mut sql = String::from(
+            "WITH raw AS (
+                SELECT s.visitor_id, s.event_type, s.created_at,
+                       (s.details->>'property_id')::int AS property_id,
+                       s.created_at - LAG(s.created_at) OVER (
+                           PARTITION BY s.visitor_id ORDER BY s.created_at
+                       ) AS gap
+                FROM stats s
+                WHERE s.tenant_id = $1
+                AND s.visitor_id IS NOT NULL
+                AND s.created_at >= ($3::timestamp AT TIME ZONE $2)
+                AND s.created_at < ($4::timestamp AT TIME ZONE $2)
+                AND ($5 OR s.details->'metadata'->'bot' IS NULL)
+             ),
+             events AS (
+                SELECT CASE WHEN $6 = 'session'
+                            THEN visitor_id || ':' || COUNT(*) FILTER (
+                                WHERE gap IS NULL OR gap > make_interval(mins => $7)
+                            ) OVER (PARTITION BY visitor_id ORDER BY created_at)
+                            ELSE visitor_id
+                       END AS unit,
+                       event_type, created_at, property_id
+                FROM raw
+             ),
+             funnel_events AS (
+                SELECT unit, event_type, created_at
+                FROM events
+                WHERE event_type = ANY($8::text[])
+                AND ($9::int IS NULL OR NOT (event_type = ANY($10::text[])) OR property_id = $9)
+             ),
+             step1 AS (
+                SELECT unit, MIN(created_at) AS started_at, MIN(created_at) AS reached_at
+                FROM funnel_events
+                WHERE event_type = ($8::text[])[1]
+                GROUP BY unit
+             )",
+        );
+        for step in 2..=steps.len() {
+            let _ = write!(
+                sql,
+                ",
+             step{step} AS (
+                SELECT p.unit, p.started_at, MIN(e.created_at) AS reached_at
+                FROM step{previous} p
+                JOIN funnel_events e ON e.unit = p.unit
+                AND e.event_type = ($8::text[])[{step}]
+                AND e.created_at > p.reached_at
+                AND e.created_at <= p.started_at + make_interval(mins => $11)
+                GROUP BY p.unit, p.started_at
+             )",
+                step = step,
+                previous = step - 1
+            );
+        }
+        let counts: Vec<String> = (1..=steps.len())
+            .map(|step| format!("SELECT {step} AS step, COUNT(*) AS units FROM step{step}"))
+            .collect();
+        let _ = write!(sql, " {} ORDER BY step", counts.join(" UNION ALL "));
+
+        let rows = sqlx::query(&sql)
+            .bind(period.tenant_id)
+            .bind(&period.timezone)
+            .bind(period.from)
+            .bind(period.to)
+            .bind(period.include_bots)
+            .bind(query.unit.as_str())
+            .bind(session_timeout_mins)
+            .bind(&steps)
+            .bind(query.property_id)
+            .bind(&property_types)
+            .bind(query.window_mins)
+            .fetch_all(&*self.pg_pool)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        Ok(rows.into_iter().map(|row| row.get("units")).collect())
+    }
+
+    async fn roll_up(&self, max_days: i64) -> Result<Option<NaiveDate>, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        // Locking the state row keeps concurrent runs from rolling up twice.
+        let rolled_up_to: Option<NaiveDate> =
+            sqlx::query_scalar("SELECT rolled_up_to FROM stats_rollup_state FOR UPDATE")
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(ApiError::DatabaseError)?;
+
+        let first_day = match rolled_up_to {
+            Some(day) => day + Duration::days(1),
+            None => {
+                let first_event: Option<NaiveDate> = sqlx::query_scalar(
+                    "SELECT (MIN(created_at) AT TIME ZONE 'UTC')::date FROM stats",
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(ApiError::DatabaseError)?;
+
+                match first_event {
+                    Some(day) => day,
+                    None => return Ok(None),
+                }
+            }
+        };
+        let yesterday = Utc::now().date_naive() - Duration::days(1);
+        if first_day > yesterday {
+            return Ok(None);
+        }
+        let last_day = yesterday.min(first_day + Duration::days(max_days - 1));
+
+        for rollup in rollups() {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE day BETWEEN $1 AND $2",
+                rollup.table
+            ))
+            .bind(first_day)
+            .bind(last_day)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+            sqlx::query(&rollup.insert())
+                .bind(first_day)
+                .bind(last_day)
+                .execute(&mut *tx)
+                .await
+                .map_err(ApiError::DatabaseError)?;
+        }
+
+        sqlx::query("UPDATE stats_rollup_state SET rolled_up_to = $1")
+            .bind(last_day)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(Some(last_day))
+    }
+
+    async fn purge_events(&self, created_before: DateTime<Utc>) -> Result<u64, ApiError> {
+        // Events not rolled up yet are kept whatever their age.
+        let result = sqlx::query(
+            "DELETE FROM stats
+             WHERE created_at < LEAST(
+                $1,
+                COALESCE(
+                    ((SELECT rolled_up_to FROM stats_rollup_state) + 1)::timestamp AT TIME ZONE 'UTC',
+                    '-infinity'::timestamptz
+                )
+             )",
+        )
+        .bind(created_before)
+        .execute(&*self.pg_pool)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn daily_salt(&self, day: NaiveDate, candidate: &str) -> Result<String, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        sqlx::query("DELETE FROM stats_salts WHERE day < $1")
+            .bind(day)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        // Concurrent callers may race to create the salt; all end up with the winner's.
+        sqlx::query(
+            "INSERT INTO stats_salts (day, salt) VALUES ($1, $2) ON CONFLICT (day) DO NOTHING",
+        )
+        .bind(day)
+        .bind(candidate)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+        let salt: String = sqlx::query_scalar("SELECT salt FROM stats_salts WHERE day = $1")
+            .bind(day)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(salt)
+    }
 }
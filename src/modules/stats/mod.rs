@@ -3,6 +3,11 @@ pub use api::*;
 
 pub mod port;
 
+mod attribution;
+
+mod event;
+pub use event::*;
+
 mod model;
 pub use model::*;
 
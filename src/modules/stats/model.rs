@@ -1,31 +1,59 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Clone, Debug, Serialize, Deserialize, sqlx::Type)]
-#[serde(rename_all = "snake_case")]
-#[sqlx(type_name = "event_type", rename_all = "snake_case")]
-pub enum EventType {
-    PropertyVisited,
-    LandingVisited,
-}
-
-pub struct PropertyVisited {
-    pub property_id: i32,
-    pub metadata: Metadata,
-}
+use super::EventType;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Metadata {
     pub referrer: Option<String>,
     pub device_type: Option<String>, // e.g., "mobile", "desktop"
-    pub ip_address: Option<String>,  // IP address of the visitor
-    pub user_agent: Option<String>,  // User agent string of the browser
+    /// Only found on events recorded before visitors were pseudonymized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>, // User agent string of the browser
+    /// Set when the event looks automated; such events are left out of reports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<Attribution>,
+}
+
+/// Where a visit came from. Values are lowercase, such as `google` /
+/// `organic` or `facebook` / `paid_social` / `summer_sale`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+    pub source: String,
+    pub medium: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+}
+
+/// Why an event was classified as bot traffic.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotReason {
+    /// The user agent matches a known crawler, monitor or HTTP library.
+    Signature,
+    MissingUserAgent,
+    /// Sent without `Referer` nor `Origin`, which scripts on a page always add.
+    NotFromPage,
+    /// Part of a burst no person browsing could produce.
+    Burst,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LandingVisited {
-    pub metadata: Metadata,
+/// Who reported an event. Kept in memory only: what gets stored is a hash
+/// of the id salted with the salt of the day.
+#[derive(Debug, Clone, Default)]
+pub struct Visitor {
+    /// Id from the site's first-party cookie, when it sent one.
+    pub id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// `Origin` or, failing that, `Referer` header of the request.
+    pub origin: Option<String>,
+    /// URL of the page the event happened on, with its query string.
+    pub page_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -34,9 +62,304 @@ pub struct Stats {
     pub event_type: EventType,
     pub tenant_id: i32,
     pub details: Option<serde_json::Value>,
+    /// Salted hash identifying the visitor for the day the event happened.
+    pub visitor_id: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+/// Width of the buckets of a time series, named after Postgres `date_trunc` fields.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+            Bucket::Month => "month",
+        }
+    }
+
+    /// Widest date range, in days, a series with these buckets may cover.
+    pub fn max_range_days(&self) -> i64 {
+        match self {
+            Bucket::Hour => 31,
+            Bucket::Day => 366,
+            Bucket::Week | Bucket::Month => 3 * 366,
+        }
+    }
+}
+
+/// Events of one type in `[from, to)`, both local times in `timezone`.
+#[derive(Debug, Clone)]
+pub struct SeriesQuery {
+    pub tenant_id: i32,
+    pub event_type: EventType,
+    pub property_id: Option<i32>,
+    pub bucket: Bucket,
+    pub timezone: String,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub include_bots: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesPoint {
+    /// Local time in the requested timezone at which the bucket starts.
+    pub bucket_start: NaiveDateTime,
+    pub visits: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total: i64,
+    pub points: Vec<SeriesPoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VisitsTimeSeries {
+    pub event_type: EventType,
+    pub property_id: Option<i32>,
+    pub bucket: Bucket,
+    pub timezone: String,
+    pub current: Series,
+    /// Period of the same length right before `current`.
+    pub previous: Series,
+    /// Relative change of the total against the previous period, in percent.
+    /// `None` when the previous period had no visits.
+    pub change_pct: Option<f64>,
+}
+
+/// Local times in `timezone` delimiting `[from, to)`.
+#[derive(Debug, Clone)]
+pub struct PeriodQuery {
+    pub tenant_id: i32,
+    pub timezone: String,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub include_bots: bool,
+}
+
+/// Counts behind `VisitorSummary`, as read from the events.
+#[derive(Debug, Clone, Default)]
+pub struct VisitorCounts {
+    pub page_views: i64,
+    pub unique_visitors: i64,
+    pub sessions: i64,
+    /// Sessions with a single page view.
+    pub bounces: i64,
+    /// Page views that could be tied to a visitor.
+    pub session_page_views: i64,
+}
+
+/// Audience of a tenant's sites over a period. Sessions end after 30 minutes
+/// of inactivity. Visitor ids change daily, so a visitor returning on another
+/// day counts again.
+#[derive(Debug, Clone, Serialize)]
+pub struct VisitorSummary {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub page_views: i64,
+    pub unique_visitors: i64,
+    pub sessions: i64,
+    /// Share of sessions with a single page view, from 0 to 1.
+    pub bounce_rate: Option<f64>,
+    pub pages_per_session: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventTotals {
+    pub event_type: EventType,
+    pub events: i64,
+    pub unique_visitors: i64,
+}
+
+/// Events of every type in the catalogue over a period, zero included.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventsOverview {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub events: Vec<EventTotals>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakdownEntry {
+    /// `None` groups the events lacking the value.
+    pub key: Option<String>,
+    pub events: i64,
+    pub unique_visitors: i64,
+}
+
+/// Events of one type over a period, broken down by the value that matters
+/// for the type, such as the listing, the channel or the searched city.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventTypeReport {
+    pub event_type: EventType,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub events: i64,
+    pub unique_visitors: i64,
+    /// Dotted path within `details` of the breakdown key.
+    pub breakdown_by: String,
+    pub breakdown: Vec<BreakdownEntry>,
+}
+
+/// What a funnel follows through its steps.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunnelUnit {
+    Visitor,
+    /// A visitor's events until 30 minutes of inactivity.
+    Session,
+}
+
+impl FunnelUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FunnelUnit::Visitor => "visitor",
+            FunnelUnit::Session => "session",
+        }
+    }
+}
+
+/// Ordered steps a unit must go through within `window_mins` of the first one.
+#[derive(Debug, Clone)]
+pub struct FunnelQuery {
+    pub period: PeriodQuery,
+    pub steps: Vec<EventType>,
+    pub unit: FunnelUnit,
+    pub window_mins: i32,
+    /// Restricts the steps about listings to this one.
+    pub property_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunnelStep {
+    pub event_type: EventType,
+    /// Units that reached this step having gone through the previous ones in order.
+    pub count: i64,
+    /// Share of the previous step's units that reached this one, from 0 to 1.
+    pub conversion_rate: Option<f64>,
+    /// Share of the first step's units that reached this one, from 0 to 1.
+    pub overall_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunnelReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub unit: FunnelUnit,
+    pub window_mins: i32,
+    pub property_id: Option<i32>,
+    pub steps: Vec<FunnelStep>,
+}
+
+/// How an attribution report groups visits.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributionGrouping {
+    /// Per source and medium.
+    Source,
+    /// Per source, medium and campaign, leaving out visits from no campaign.
+    Campaign,
+}
+
+/// Counts behind an `AttributionRow`, as read from the events.
+#[derive(Debug, Clone)]
+pub struct AttributionCounts {
+    pub source: String,
+    pub medium: String,
+    pub campaign: Option<String>,
+    pub visits: i64,
+    pub contacts: i64,
+    pub leads: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionRow {
+    pub source: String,
+    pub medium: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+    /// Landing and listing page views.
+    pub visits: i64,
+    /// WhatsApp and phone button clicks.
+    pub contacts: i64,
+    /// Lead forms submitted.
+    pub leads: i64,
+    /// Leads per visit.
+    pub lead_rate: Option<f64>,
+}
+
+/// Visits and leads per traffic source over a period. Events inherit the
+/// source the visitor arrived from that day, so leads are credited to the
+/// campaign that brought them. Events recorded before sources were tracked
+/// are grouped under `(not set)`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub group_by: AttributionGrouping,
+    pub rows: Vec<AttributionRow>,
+}
+
+/// A listing's numbers over a period.
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyAnalytics {
+    pub property_id: i32,
+    pub title: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub views: i64,
+    /// Visitors are told apart within a day, so one coming back on another
+    /// day counts again.
+    pub unique_visitors: i64,
+    pub contact_clicks: i64,
+    /// Every event type about listings, zero included.
+    pub events: Vec<EventTotals>,
+    pub views_series: VisitsTimeSeries,
+    /// Views per referring host; `None` groups direct views.
+    pub referrers: Vec<BreakdownEntry>,
+}
+
+/// What a stats export contains.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsExportKind {
+    /// One row per event. Only events not purged yet can be exported.
+    Raw,
+    /// Events and visitors per day, event type and listing.
+    #[default]
+    Daily,
+}
+
+impl StatsExportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsExportKind::Raw => "raw",
+            StatsExportKind::Daily => "daily",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyEventCount {
+    pub day: NaiveDate,
+    pub event_type: EventType,
+    pub property_id: Option<i32>,
+    pub events: i64,
+    pub unique_visitors: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Info {
     pub visit_count: i32,
@@ -2,19 +2,120 @@ use async_trait::async_trait;
 
 use crate::error::ApiError;
 
-use super::{LandingVisitedInfo, PropertyVisitedInfo, Stats};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::BoxStream;
+
+use super::{
+    Attribution, AttributionCounts, AttributionGrouping, BreakdownEntry, DailyEventCount,
+    EventTotals, EventType, FunnelQuery, LandingVisitedInfo, PeriodQuery, PropertyVisitedInfo,
+    SeriesPoint, SeriesQuery, Stats, VisitorCounts,
+};
 
 #[async_trait]
 pub trait DBRepository: Send + Sync {
     async fn create(&self, stats: Stats) -> Result<Stats, ApiError>;
 
+    /// Aggregations leave out bot traffic unless `include_bots` is set.
     async fn get_property_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<Vec<PropertyVisitedInfo>, ApiError>;
 
     async fn get_landing_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<LandingVisitedInfo, ApiError>;
+
+    /// Visits per bucket, including empty buckets, in chronological order.
+    async fn visits_series(&self, query: &SeriesQuery) -> Result<Vec<SeriesPoint>, ApiError>;
+
+    /// Page views, visitors and sessions, grouping a visitor's events into
+    /// sessions that end after `session_timeout_mins` of inactivity.
+    async fn visitor_counts(
+        &self,
+        query: &PeriodQuery,
+        session_timeout_mins: i32,
+    ) -> Result<VisitorCounts, ApiError>;
+
+    // Reports by day and longer read the daily rollups, plus the raw events
+    // not rolled up yet. The rollups hold UTC days, so in any other timezone
+    // these reports are counted from the raw events alone.
+
+    /// Events and unique visitors per event type, for the types seen in the period.
+    async fn event_totals(&self, query: &PeriodQuery) -> Result<Vec<EventTotals>, ApiError>;
+
+    /// Events of `event_type` grouped by the value at its
+    /// `EventType::breakdown_path`, the `limit` most frequent first.
+    async fn event_breakdown(
+        &self,
+        query: &PeriodQuery,
+        event_type: EventType,
+        limit: i64,
+    ) -> Result<Vec<BreakdownEntry>, ApiError>;
+
+    /// Events and unique visitors per event type about `property_id`.
+    async fn property_event_totals(
+        &self,
+        query: &PeriodQuery,
+        property_id: i32,
+    ) -> Result<Vec<EventTotals>, ApiError>;
+
+    /// Views of `property_id` per referring host, the `limit` most frequent first.
+    async fn property_referrers(
+        &self,
+        query: &PeriodQuery,
+        property_id: i32,
+        limit: i64,
+    ) -> Result<Vec<BreakdownEntry>, ApiError>;
+
+    /// Counts per day, event type and listing, in that order.
+    fn stream_daily_counts(
+        &self,
+        query: &PeriodQuery,
+    ) -> BoxStream<'static, Result<DailyEventCount, ApiError>>;
+
+    /// Fails with `BadRequest` unless Postgres knows the time zone `timezone`.
+    async fn check_timezone(&self, timezone: &str) -> Result<(), ApiError>;
+
+    /// Raw events of the period in local time, oldest first.
+    fn stream_events(&self, query: &PeriodQuery) -> BoxStream<'static, Result<Stats, ApiError>>;
+
+    /// Visits, contacts and leads per source as grouped by `group_by`, the
+    /// `limit` with most visits first.
+    async fn attribution_counts(
+        &self,
+        query: &PeriodQuery,
+        group_by: AttributionGrouping,
+        limit: i64,
+    ) -> Result<Vec<AttributionCounts>, ApiError>;
+
+    /// Attribution of the latest event of `visitor_id` since `since`, if any.
+    async fn last_attribution(
+        &self,
+        tenant_id: i32,
+        visitor_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<Attribution>, ApiError>;
+
+    /// Units reaching each step of the funnel, in step order. Steps are
+    /// matched to the earliest event following the previous step.
+    async fn funnel_counts(
+        &self,
+        query: &FunnelQuery,
+        session_timeout_mins: i32,
+    ) -> Result<Vec<i64>, ApiError>;
+
+    /// Rolls up to `max_days` complete UTC days after the last one rolled up,
+    /// returning the new last day if any was.
+    async fn roll_up(&self, max_days: i64) -> Result<Option<NaiveDate>, ApiError>;
+
+    /// Deletes the raw events created before `created_before` that are
+    /// already rolled up, returning how many were.
+    async fn purge_events(&self, created_before: DateTime<Utc>) -> Result<u64, ApiError>;
+
+    /// Salt for `day`, storing `candidate` if the day has none yet. Salts of
+    /// earlier days are deleted.
+    async fn daily_salt(&self, day: NaiveDate, candidate: &str) -> Result<String, ApiError>;
 }
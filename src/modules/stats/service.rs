@@ -1,33 +1,646 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use crate::error::ApiError;
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime, Utc};
+use futures::stream::BoxStream;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-use super::{port::DBRepository, LandingVisitedInfo, PropertyVisitedInfo, Stats};
+use crate::{
+    error::ApiError,
+    modules::property,
+    utils::{rate_limit::RateLimiter, user_agent},
+};
+
+use super::{
+    attribution::{self, INTERNAL},
+    port::DBRepository,
+    Attribution, AttributionGrouping, AttributionReport, AttributionRow, BotReason, BreakdownEntry,
+    Bucket, DailyEventCount, EventTotals, EventType, EventTypeReport, EventsOverview, FunnelQuery,
+    FunnelReport, FunnelStep, LandingVisitedInfo, Metadata, PeriodQuery, PropertyAnalytics,
+    PropertyVisitedInfo, Series, SeriesQuery, Stats, VisitEvent, Visitor, VisitorSummary,
+    VisitsTimeSeries,
+};
+
+/// Events a single IP may report per minute before being throttled.
+const EVENTS_PER_MINUTE: u32 = 60;
+/// More events than this from one IP within `BURST_WINDOW` are taken as automated.
+const BURST_EVENTS: u32 = 10;
+const BURST_WINDOW: Duration = Duration::from_secs(5);
+/// Inactivity after which a visitor's next event starts a new session.
+const SESSION_TIMEOUT_MINS: i32 = 30;
+const MAX_PERIOD_DAYS: i64 = 366;
+/// Keys listed in an event type report, most frequent first.
+const BREAKDOWN_LIMIT: i64 = 50;
+const MAX_FUNNEL_STEPS: usize = 6;
+/// Sources or campaigns listed in an attribution report, most visited first.
+const ATTRIBUTION_LIMIT: i64 = 100;
+/// Days rolled up per transaction, so catching up does not hold locks for long.
+const ROLLUP_BATCH_DAYS: i64 = 7;
+/// Timezone of the days in the rollups. Daily reports in any other one are
+/// counted from raw events.
+const ROLLUP_TIMEZONE: &str = "UTC";
 
 pub struct Service {
     db_repo: Arc<dyn DBRepository>,
+    property_service: Arc<property::Service>,
+    rate_limiter: RateLimiter,
+    burst_detector: RateLimiter,
+    /// Days raw events are kept once rolled up.
+    retention_days: i64,
+    /// Salt of the current UTC day, cached to spare a query per event.
+    salt: Mutex<Option<(NaiveDate, String)>>,
 }
 
 impl Service {
-    pub fn new(db_repo: Arc<dyn DBRepository>) -> Self {
-        Self { db_repo }
+    pub fn new(
+        db_repo: Arc<dyn DBRepository>,
+        property_service: Arc<property::Service>,
+        retention_days: i64,
+    ) -> Self {
+        Self {
+            db_repo,
+            property_service,
+            rate_limiter: RateLimiter::new(EVENTS_PER_MINUTE, Duration::from_secs(60)),
+            burst_detector: RateLimiter::new(BURST_EVENTS, BURST_WINDOW),
+            retention_days,
+            salt: Mutex::new(None),
+        }
+    }
+
+    /// Records an event on behalf of the tenant itself, unthrottled.
+    pub async fn create(
+        &self,
+        tenant_id: i32,
+        event: VisitEvent,
+        metadata: Metadata,
+    ) -> Result<Stats, ApiError> {
+        self.insert(tenant_id, event, metadata, None).await
+    }
+
+    /// Records an anonymous visitor's event, throttled per IP.
+    pub async fn record_visit(
+        &self,
+        tenant_id: i32,
+        event: VisitEvent,
+        visitor: Visitor,
+        mut metadata: Metadata,
+    ) -> Result<Stats, ApiError> {
+        let ip_address = visitor.ip_address.as_deref().unwrap_or("unknown");
+        self.rate_limiter.check(ip_address)?;
+        let burst = self.burst_detector.check(ip_address).is_err();
+        metadata.bot = bot_reason(&visitor, burst);
+
+        let visitor_id = self.visitor_id(tenant_id, &visitor).await?;
+        metadata.attribution = Some(
+            self.attribution(tenant_id, &visitor, &metadata, visitor_id.as_deref())
+                .await?,
+        );
+        self.insert(tenant_id, event, metadata, visitor_id).await
+    }
+
+    /// Source of the visit. Moving between pages of the site keeps the source
+    /// the visitor arrived from that day, so later events such as leads are
+    /// credited to it.
+    async fn attribution(
+        &self,
+        tenant_id: i32,
+        visitor: &Visitor,
+        metadata: &Metadata,
+        visitor_id: Option<&str>,
+    ) -> Result<Attribution, ApiError> {
+        let attribution =
+            attribution::attribute(visitor.page_url.as_deref(), metadata.referrer.as_deref());
+        if attribution.medium != INTERNAL {
+            return Ok(attribution);
+        }
+
+        let arrival = match visitor_id {
+            Some(visitor_id) => {
+                let today = Utc::now()
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap_or_default();
+                self.db_repo
+                    .last_attribution(tenant_id, visitor_id, today.and_utc())
+                    .await?
+            }
+            None => None,
+        };
+        Ok(arrival.unwrap_or_else(Attribution::direct))
+    }
+
+    /// Validates `event` and stores it. Events about a listing must refer to
+    /// a published listing of the tenant.
+    async fn insert(
+        &self,
+        tenant_id: i32,
+        event: VisitEvent,
+        metadata: Metadata,
+        visitor_id: Option<String>,
+    ) -> Result<Stats, ApiError> {
+        event.validate()?;
+
+        if let Some(property_id) = event.property_id() {
+            let property = self
+                .property_service
+                .find_property_by_id(property_id)
+                .await?;
+            if property.property.tenant_id != tenant_id {
+                return Err(ApiError::NotFound(format!(
+                    "Property with id {} not found",
+                    property_id
+                )));
+            }
+        }
+
+        self.db_repo
+            .create(Stats {
+                id: 0,
+                event_type: event.event_type(),
+                tenant_id,
+                details: Some(event.details(&metadata)?),
+                visitor_id,
+                created_at: Utc::now(),
+            })
+            .await
+    }
+
+    /// Rolls up every complete day not rolled up yet, returning the last one.
+    pub async fn roll_up(&self) -> Result<Option<NaiveDate>, ApiError> {
+        let mut rolled_up_to = None;
+        while let Some(day) = self.db_repo.roll_up(ROLLUP_BATCH_DAYS).await? {
+            rolled_up_to = Some(day);
+        }
+        Ok(rolled_up_to)
     }
 
-    pub async fn create(&self, stats: Stats) -> Result<Stats, ApiError> {
-        self.db_repo.create(stats).await
+    /// Deletes raw events older than the retention period once they are
+    /// rolled up. Reports read from raw events only reach back this far.
+    pub async fn purge_events(&self) -> Result<u64, ApiError> {
+        self.db_repo
+            .purge_events(Utc::now() - ChronoDuration::days(self.retention_days))
+            .await
+    }
+
+    /// Fails unless every raw event from `from` on is still kept. Purging cuts
+    /// at an instant of the first retained day, and a local day can start up
+    /// to a day before the UTC one, hence the two days of margin.
+    fn ensure_raw_events(&self, from: NaiveDateTime) -> Result<(), ApiError> {
+        let since = Utc::now().date_naive() - ChronoDuration::days(self.retention_days - 2);
+        if from.date() < since {
+            return Err(ApiError::BadRequest(format!(
+                "This report is counted from raw events, which are kept since {}",
+                since
+            )));
+        }
+        Ok(())
+    }
+
+    /// Daily reports read the rollups, whose days are UTC days. In any other
+    /// timezone they are counted from raw events, as far back as these are kept.
+    async fn ensure_daily_counts(
+        &self,
+        timezone: &str,
+        from: NaiveDateTime,
+    ) -> Result<(), ApiError> {
+        if timezone == ROLLUP_TIMEZONE {
+            return Ok(());
+        }
+        self.db_repo.check_timezone(timezone).await?;
+        self.ensure_raw_events(from)
+    }
+
+    /// Pseudonymous id for `visitor`, stable for the day within a tenant.
+    /// Falls back to the IP and user agent when the site sent no id.
+    async fn visitor_id(
+        &self,
+        tenant_id: i32,
+        visitor: &Visitor,
+    ) -> Result<Option<String>, ApiError> {
+        let key = match (&visitor.id, &visitor.ip_address, &visitor.user_agent) {
+            (Some(id), _, _) => format!("id:{}", id),
+            (None, None, None) => return Ok(None),
+            (None, ip_address, user_agent) => format!(
+                "ip:{}|{}",
+                ip_address.as_deref().unwrap_or_default(),
+                user_agent.as_deref().unwrap_or_default()
+            ),
+        };
+
+        let salt = self.daily_salt().await?;
+        let mut hasher = Sha256::new();
+        hasher.update(salt.as_bytes());
+        hasher.update(tenant_id.to_be_bytes());
+        hasher.update(key.as_bytes());
+        Ok(Some(hex::encode(hasher.finalize())))
+    }
+
+    async fn daily_salt(&self) -> Result<String, ApiError> {
+        let today = Utc::now().date_naive();
+        if let Some((day, salt)) = self.salt.lock().unwrap().as_ref() {
+            if *day == today {
+                return Ok(salt.clone());
+            }
+        }
+
+        let candidate = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let salt = self.db_repo.daily_salt(today, &candidate).await?;
+        *self.salt.lock().unwrap() = Some((today, salt.clone()));
+        Ok(salt)
+    }
+
+    /// Unique visitors, sessions and engagement over `query`'s range.
+    pub async fn get_visitor_summary(
+        &self,
+        query: PeriodQuery,
+    ) -> Result<VisitorSummary, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_raw_events(query.from)?;
+
+        let counts = self
+            .db_repo
+            .visitor_counts(&query, SESSION_TIMEOUT_MINS)
+            .await?;
+        let per_session =
+            |value: i64| (counts.sessions > 0).then(|| value as f64 / counts.sessions as f64);
+
+        Ok(VisitorSummary {
+            from: query.from.date(),
+            to: last_day(query.to),
+            page_views: counts.page_views,
+            unique_visitors: counts.unique_visitors,
+            sessions: counts.sessions,
+            bounce_rate: per_session(counts.bounces),
+            pages_per_session: per_session(counts.session_page_views),
+        })
     }
 
     pub async fn get_property_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<Vec<PropertyVisitedInfo>, ApiError> {
-        self.db_repo.get_property_visited_info(tenant_id).await
+        self.db_repo
+            .get_property_visited_info(tenant_id, include_bots)
+            .await
+    }
+
+    /// Totals of every event type in the catalogue over `query`'s range.
+    pub async fn get_events_overview(
+        &self,
+        query: PeriodQuery,
+    ) -> Result<EventsOverview, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_daily_counts(&query.timezone, query.from)
+            .await?;
+
+        let totals = self.db_repo.event_totals(&query).await?;
+        let events = with_zeros(&totals, EventType::ALL.iter());
+
+        Ok(EventsOverview {
+            from: query.from.date(),
+            to: last_day(query.to),
+            events,
+        })
+    }
+
+    pub async fn get_event_type_report(
+        &self,
+        query: PeriodQuery,
+        event_type: EventType,
+    ) -> Result<EventTypeReport, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_daily_counts(&query.timezone, query.from)
+            .await?;
+
+        let path = event_type.breakdown_path();
+        let breakdown: Vec<BreakdownEntry> = self
+            .db_repo
+            .event_breakdown(&query, event_type, BREAKDOWN_LIMIT)
+            .await?;
+        let totals = self
+            .db_repo
+            .event_totals(&query)
+            .await?
+            .into_iter()
+            .find(|totals| totals.event_type == event_type);
+
+        Ok(EventTypeReport {
+            event_type,
+            from: query.from.date(),
+            to: last_day(query.to),
+            events: totals.as_ref().map_or(0, |totals| totals.events),
+            unique_visitors: totals.as_ref().map_or(0, |totals| totals.unique_visitors),
+            breakdown_by: path.join("."),
+            breakdown,
+        })
+    }
+
+    /// Views, visitors, referrers and interactions of one of the tenant's listings.
+    pub async fn get_property_analytics(
+        &self,
+        query: PeriodQuery,
+        property_id: i32,
+        bucket: Bucket,
+    ) -> Result<PropertyAnalytics, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_daily_counts(&query.timezone, query.from)
+            .await?;
+
+        let property = self
+            .property_service
+            .find_property_by_id(property_id)
+            .await?;
+        if property.property.tenant_id != query.tenant_id {
+            return Err(ApiError::NotFound(format!(
+                "Property with id {} not found",
+                property_id
+            )));
+        }
+
+        let views_series = self
+            .get_visits_time_series(SeriesQuery {
+                tenant_id: query.tenant_id,
+                event_type: EventType::PropertyVisited,
+                property_id: Some(property_id),
+                bucket,
+                timezone: query.timezone.clone(),
+                from: query.from,
+                to: query.to,
+                include_bots: query.include_bots,
+            })
+            .await?;
+        let totals = self
+            .db_repo
+            .property_event_totals(&query, property_id)
+            .await?;
+        let referrers = self
+            .db_repo
+            .property_referrers(&query, property_id, BREAKDOWN_LIMIT)
+            .await?;
+
+        let events = with_zeros(
+            &totals,
+            EventType::ALL
+                .iter()
+                .filter(|event_type| event_type.has_property()),
+        );
+        let totals_of = |event_type: EventType| {
+            events
+                .iter()
+                .find(|totals| totals.event_type == event_type)
+                .map_or((0, 0), |totals| (totals.events, totals.unique_visitors))
+        };
+        let (views, unique_visitors) = totals_of(EventType::PropertyVisited);
+
+        Ok(PropertyAnalytics {
+            property_id,
+            title: property.property.title,
+            from: query.from.date(),
+            to: last_day(query.to),
+            views,
+            unique_visitors,
+            contact_clicks: totals_of(EventType::ContactClicked).0,
+            events,
+            views_series,
+            referrers,
+        })
+    }
+
+    /// Counts per day, event type and listing over `query`'s range, for export.
+    pub async fn export_daily_counts(
+        &self,
+        query: PeriodQuery,
+    ) -> Result<BoxStream<'static, Result<DailyEventCount, ApiError>>, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_daily_counts(&query.timezone, query.from)
+            .await?;
+        Ok(self.db_repo.stream_daily_counts(&query))
+    }
+
+    /// Raw events over `query`'s range, for export. Events older than the
+    /// retention period are gone and only remain in the daily counts.
+    pub async fn export_events(
+        &self,
+        query: PeriodQuery,
+    ) -> Result<BoxStream<'static, Result<Stats, ApiError>>, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_raw_events(query.from)?;
+        self.db_repo.check_timezone(&query.timezone).await?;
+        Ok(self.db_repo.stream_events(&query))
+    }
+
+    /// Visits, contacts and leads per source or campaign over `query`'s range.
+    pub async fn get_attribution_report(
+        &self,
+        query: PeriodQuery,
+        group_by: AttributionGrouping,
+    ) -> Result<AttributionReport, ApiError> {
+        validate_range(query.from, query.to, MAX_PERIOD_DAYS)?;
+        self.ensure_daily_counts(&query.timezone, query.from)
+            .await?;
+
+        let rows = self
+            .db_repo
+            .attribution_counts(&query, group_by, ATTRIBUTION_LIMIT)
+            .await?
+            .into_iter()
+            .map(|counts| AttributionRow {
+                lead_rate: (counts.visits > 0).then(|| counts.leads as f64 / counts.visits as f64),
+                source: counts.source,
+                medium: counts.medium,
+                campaign: counts.campaign,
+                visits: counts.visits,
+                contacts: counts.contacts,
+                leads: counts.leads,
+            })
+            .collect();
+
+        Ok(AttributionReport {
+            from: query.from.date(),
+            to: last_day(query.to),
+            group_by,
+            rows,
+        })
+    }
+
+    /// How many visitors or sessions go through `query`'s steps in order.
+    pub async fn get_funnel(&self, query: FunnelQuery) -> Result<FunnelReport, ApiError> {
+        validate_range(query.period.from, query.period.to, MAX_PERIOD_DAYS)?;
+        self.ensure_raw_events(query.period.from)?;
+        if !(2..=MAX_FUNNEL_STEPS).contains(&query.steps.len()) {
+            return Err(ApiError::BadRequest(format!(
+                "A funnel has between 2 and {} steps",
+                MAX_FUNNEL_STEPS
+            )));
+        }
+        if query.property_id.is_some()
+            && !query
+                .steps
+                .iter()
+                .any(|event_type| event_type.has_property())
+        {
+            return Err(ApiError::BadRequest(
+                "`property_id` needs a step about listings".into(),
+            ));
+        }
+
+        let counts = self
+            .db_repo
+            .funnel_counts(&query, SESSION_TIMEOUT_MINS)
+            .await?;
+        let rate = |count: i64, base: i64| (base > 0).then(|| count as f64 / base as f64);
+        let first = counts.first().copied().unwrap_or_default();
+
+        let steps = query
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, event_type)| {
+                let count = counts.get(i).copied().unwrap_or_default();
+                let previous = match i {
+                    0 => count,
+                    _ => counts.get(i - 1).copied().unwrap_or_default(),
+                };
+                FunnelStep {
+                    event_type: *event_type,
+                    count,
+                    conversion_rate: rate(count, previous),
+                    overall_rate: rate(count, first),
+                }
+            })
+            .collect();
+
+        Ok(FunnelReport {
+            from: query.period.from.date(),
+            to: last_day(query.period.to),
+            unit: query.unit,
+            window_mins: query.window_mins,
+            property_id: query.property_id,
+            steps,
+        })
+    }
+
+    /// Visits over `query`'s range next to the same-length period right before it.
+    pub async fn get_visits_time_series(
+        &self,
+        query: SeriesQuery,
+    ) -> Result<VisitsTimeSeries, ApiError> {
+        let range = validate_range(query.from, query.to, query.bucket.max_range_days())?;
+        if query.property_id.is_some() && !query.event_type.has_property() {
+            return Err(ApiError::BadRequest(
+                "`property_id` does not apply to this event type".into(),
+            ));
+        }
+
+        let previous_query = SeriesQuery {
+            from: query.from - range,
+            to: query.from,
+            ..query.clone()
+        };
+        // Hourly buckets are always counted from raw events.
+        if matches!(query.bucket, Bucket::Hour) {
+            self.ensure_raw_events(previous_query.from)?;
+        } else {
+            self.ensure_daily_counts(&query.timezone, previous_query.from)
+                .await?;
+        }
+
+        let current = self.series(&query).await?;
+        let previous = self.series(&previous_query).await?;
+        let change_pct = (previous.total > 0)
+            .then(|| (current.total - previous.total) as f64 / previous.total as f64 * 100.0);
+
+        Ok(VisitsTimeSeries {
+            event_type: query.event_type,
+            property_id: query.property_id,
+            bucket: query.bucket,
+            timezone: query.timezone,
+            current,
+            previous,
+            change_pct,
+        })
+    }
+
+    async fn series(&self, query: &SeriesQuery) -> Result<Series, ApiError> {
+        let points = self.db_repo.visits_series(query).await?;
+
+        Ok(Series {
+            from: query.from.date(),
+            to: last_day(query.to),
+            total: points.iter().map(|point| point.visits).sum(),
+            points,
+        })
     }
 
     pub async fn get_landing_visited_info(
         &self,
         tenant_id: i32,
+        include_bots: bool,
     ) -> Result<LandingVisitedInfo, ApiError> {
-        self.db_repo.get_landing_visited_info(tenant_id).await
+        self.db_repo
+            .get_landing_visited_info(tenant_id, include_bots)
+            .await
+    }
+}
+
+/// Checks `[from, to)` is not empty nor longer than `max_days`, returning its length.
+fn validate_range(
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    max_days: i64,
+) -> Result<ChronoDuration, ApiError> {
+    let range = to - from;
+    if range <= ChronoDuration::zero() {
+        return Err(ApiError::BadRequest(
+            "`to` must not be before `from`".into(),
+        ));
+    }
+    if range.num_days() > max_days {
+        return Err(ApiError::BadRequest(format!(
+            "The range can span at most {} days",
+            max_days
+        )));
+    }
+    Ok(range)
+}
+
+/// Totals of each of `event_types`, zero for the ones missing from `totals`.
+fn with_zeros<'a>(
+    totals: &[EventTotals],
+    event_types: impl Iterator<Item = &'a EventType>,
+) -> Vec<EventTotals> {
+    event_types
+        .map(|event_type| {
+            totals
+                .iter()
+                .find(|totals| totals.event_type == *event_type)
+                .cloned()
+                .unwrap_or(EventTotals {
+                    event_type: *event_type,
+                    events: 0,
+                    unique_visitors: 0,
+                })
+        })
+        .collect()
+}
+
+/// Last day covered by a period ending, exclusively, at `to`.
+fn last_day(to: NaiveDateTime) -> NaiveDate {
+    (to - ChronoDuration::days(1)).date()
+}
+
+/// Why the event looks automated, if it does. Bot traffic is still recorded
+/// so it can be reported on request.
+fn bot_reason(visitor: &Visitor, burst: bool) -> Option<BotReason> {
+    match visitor.user_agent.as_deref() {
+        None | Some("") => Some(BotReason::MissingUserAgent),
+        Some(user_agent) if user_agent::is_bot(user_agent) => Some(BotReason::Signature),
+        _ if visitor.origin.is_none() => Some(BotReason::NotFromPage),
+        _ if burst => Some(BotReason::Burst),
+        _ => None,
     }
 }
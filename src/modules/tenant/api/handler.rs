@@ -1,23 +1,55 @@
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{http::header::ETag, web, HttpRequest, HttpResponse, Responder};
 use serde::Deserialize;
 use std::sync::Arc;
+use validator::Validate;
 
-use crate::{error::ApiError, modules::tenant::Service, utils::lucia};
+use crate::{
+    error::ApiError,
+    modules::tenant::{Service, TenantPatch},
+    utils::{
+        etag::{entity_tag, Precondition},
+        lucia,
+        patch::{non_null, nullable},
+    },
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateTenantRequest {
+    #[validate(length(max = 255))]
     pub company_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
     pub first_name: String,
+    #[validate(length(min = 1, max = 100))]
     pub last_name: String,
+    #[validate(length(min = 6, max = 50))]
     pub phone: Option<String>,
 }
 
+/// JSON merge patch body: absent members are left unchanged, `null` clears.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PatchTenantRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(max = 255))]
+    pub company_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 100))]
+    pub first_name: Option<String>,
+    #[serde(default, deserialize_with = "non_null")]
+    #[validate(length(min = 1, max = 100))]
+    pub last_name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[validate(length(min = 6, max = 50))]
+    pub phone: Option<Option<String>>,
+}
+
 pub async fn update_tenant(
     service: web::Data<Arc<Service>>,
     lucia_service: web::Data<Arc<lucia::Service>>,
     req_headers: HttpRequest,
     web::Json(update_request): web::Json<UpdateTenantRequest>,
 ) -> Result<impl Responder, ApiError> {
+    update_request.validate()?;
+
     // Extract the Authorization header
     let basic_auth_header = req_headers
         .headers()
@@ -41,9 +73,52 @@ pub async fn update_tenant(
     tenant.last_name = update_request.last_name;
     tenant.phone = update_request.phone;
 
-    let updated_tenant = service.update_tenant(tenant).await?;
+    let precondition = Precondition::from_request(&req_headers)?;
+    let updated_tenant = service
+        .update_tenant(tenant, &precondition, &session.user_id)
+        .await?;
 
-    Ok(HttpResponse::Ok().json(updated_tenant))
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(updated_tenant.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(updated_tenant))
+}
+
+pub async fn patch_tenant(
+    service: web::Data<Arc<Service>>,
+    lucia_service: web::Data<Arc<lucia::Service>>,
+    req_headers: HttpRequest,
+    web::Json(patch_request): web::Json<PatchTenantRequest>,
+) -> Result<impl Responder, ApiError> {
+    patch_request.validate()?;
+
+    let basic_auth_header = req_headers
+        .headers()
+        .get("Authorization")
+        .and_then(|header_value| header_value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".into()))?;
+
+    let session = lucia_service.get_session(basic_auth_header).await?;
+    let tenant = service.find_by_user_id(&session.user_id).await?;
+
+    let patch = TenantPatch {
+        company_name: patch_request.company_name,
+        first_name: patch_request.first_name,
+        last_name: patch_request.last_name,
+        phone: patch_request.phone,
+    };
+
+    let precondition = Precondition::from_request(&req_headers)?;
+    let patched_tenant = service
+        .patch_tenant(tenant.id, patch, &precondition, &session.user_id)
+        .await?;
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(patched_tenant.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(patched_tenant))
 }
 
 pub async fn get_tenant_by_user_id(
@@ -52,5 +127,10 @@ pub async fn get_tenant_by_user_id(
 ) -> Result<impl Responder, ApiError> {
     let user_id = user_id.into_inner();
     let tenant = service.find_by_user_id(&user_id).await?;
-    Ok(HttpResponse::Ok().json(tenant))
+
+    let mut response = HttpResponse::Ok();
+    if let Some(etag) = entity_tag(tenant.updated_at) {
+        response.insert_header(ETag(etag));
+    }
+    Ok(response.json(tenant))
 }
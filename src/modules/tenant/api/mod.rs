@@ -1,5 +1,5 @@
 use actix_web::web;
-use handler::{get_tenant_by_user_id, update_tenant};
+use handler::{get_tenant_by_user_id, patch_tenant, update_tenant};
 
 mod handler;
 
@@ -7,6 +7,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/tenant")
             .route("/{id}", web::put().to(update_tenant))
+            .route("/{id}", web::patch().to(patch_tenant))
             .route("/{user_id}", web::get().to(get_tenant_by_user_id)),
     );
 }
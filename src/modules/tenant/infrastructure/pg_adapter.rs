@@ -1,17 +1,34 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 
 use crate::{
     error::ApiError,
-    modules::tenant::{port::DBRepository, Tenant},
-    utils::database::PostgresRepository,
+    modules::tenant::{port::DBRepository, Tenant, TenantPatch},
+    utils::{
+        database::{push_set, PostgresRepository},
+        etag::Precondition,
+    },
 };
 
+/// Locks the tenant row for the rest of the transaction and returns its version.
+async fn lock_tenant(conn: &mut PgConnection, id: i32) -> Result<Option<DateTime<Utc>>, ApiError> {
+    sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT updated_at FROM tenants WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(ApiError::DatabaseError)?
+    .ok_or_else(|| ApiError::NotFound(format!("Tenant with id {} not found", id)))
+}
+
 #[async_trait]
 impl DBRepository for PostgresRepository {
     async fn find_by_user_id(&self, id: &str) -> Result<Tenant, ApiError> {
         let tenant = sqlx::query_as::<_, Tenant>(
             r#"
-            SELECT id, auth_user_id, company_name, first_name, last_name, phone
+            SELECT id, auth_user_id, company_name, first_name, last_name, phone, updated_at
             FROM tenants
             WHERE auth_user_id = $1
             "#,
@@ -29,7 +46,39 @@ impl DBRepository for PostgresRepository {
         Ok(tenant)
     }
 
-    async fn update(&self, tenant: Tenant) -> Result<Tenant, ApiError> {
+    async fn find_by_id(&self, id: i32) -> Result<Tenant, ApiError> {
+        sqlx::query_as::<_, Tenant>(
+            r#"
+            SELECT id, auth_user_id, company_name, first_name, last_name, phone, updated_at
+            FROM tenants
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&*self.pg_pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => {
+                ApiError::NotFound(format!("Tenant with id {} not found", id))
+            }
+            _ => ApiError::DatabaseError(err),
+        })
+    }
+
+    async fn update(
+        &self,
+        tenant: Tenant,
+        precondition: &Precondition,
+    ) -> Result<Tenant, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_tenant(&mut tx, tenant.id).await?;
+        precondition.ensure(current_version)?;
+
         let updated_tenant = sqlx::query_as::<_, Tenant>(
             r#"
             UPDATE tenants
@@ -38,7 +87,7 @@ impl DBRepository for PostgresRepository {
                 last_name = $3,
                 phone = $4
             WHERE id = $5
-            RETURNING id, auth_user_id, company_name, first_name, last_name, phone
+            RETURNING id, auth_user_id, company_name, first_name, last_name, phone, updated_at
             "#,
         )
         .bind(&tenant.company_name)
@@ -46,7 +95,7 @@ impl DBRepository for PostgresRepository {
         .bind(&tenant.last_name)
         .bind(&tenant.phone)
         .bind(&tenant.id)
-        .fetch_one(&*self.pg_pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|err| match err {
             sqlx::Error::RowNotFound => {
@@ -55,6 +104,51 @@ impl DBRepository for PostgresRepository {
             _ => ApiError::DatabaseError(err.into()),
         })?;
 
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
         Ok(updated_tenant)
     }
+
+    async fn patch(
+        &self,
+        id: i32,
+        patch: TenantPatch,
+        precondition: &Precondition,
+    ) -> Result<Tenant, ApiError> {
+        let mut tx = self
+            .pg_pool
+            .begin()
+            .await
+            .map_err(ApiError::DatabaseError)?;
+
+        let current_version = lock_tenant(&mut tx, id).await?;
+        precondition.ensure(current_version)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE tenants SET ");
+        let mut set = query.separated(", ");
+        push_set(&mut set, "company_name", patch.company_name);
+        push_set(&mut set, "first_name", patch.first_name);
+        push_set(&mut set, "last_name", patch.last_name);
+        push_set(&mut set, "phone", patch.phone);
+        set.push("updated_at = CURRENT_TIMESTAMP");
+
+        query.push(" WHERE id = ").push_bind(id).push(
+            " RETURNING id, auth_user_id, company_name, first_name, last_name, phone, updated_at",
+        );
+
+        let patched_tenant = query
+            .build_query_as::<Tenant>()
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::RowNotFound => {
+                    ApiError::NotFound(format!("Tenant with id {} not found", id))
+                }
+                _ => ApiError::DatabaseError(err),
+            })?;
+
+        tx.commit().await.map_err(ApiError::DatabaseError)?;
+
+        Ok(patched_tenant)
+    }
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -9,4 +10,14 @@ pub struct Tenant {
     pub first_name: String,
     pub last_name: String,
     pub phone: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Columns to change on a tenant; `Some(None)` clears a nullable column.
+#[derive(Debug, Default)]
+pub struct TenantPatch {
+    pub company_name: Option<Option<String>>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub phone: Option<Option<String>>,
 }
@@ -1,11 +1,19 @@
 use async_trait::async_trait;
 
-use crate::error::ApiError;
+use crate::{error::ApiError, utils::etag::Precondition};
 
-use super::Tenant;
+use super::{Tenant, TenantPatch};
 
 #[async_trait]
 pub trait DBRepository: Send + Sync {
     async fn find_by_user_id(&self, id: &str) -> Result<Tenant, ApiError>;
-    async fn update(&self, tenant: Tenant) -> Result<Tenant, ApiError>;
+    async fn find_by_id(&self, id: i32) -> Result<Tenant, ApiError>;
+    async fn update(&self, tenant: Tenant, precondition: &Precondition)
+        -> Result<Tenant, ApiError>;
+    async fn patch(
+        &self,
+        id: i32,
+        patch: TenantPatch,
+        precondition: &Precondition,
+    ) -> Result<Tenant, ApiError>;
 }
@@ -1,22 +1,71 @@
 use std::sync::Arc;
 
-use crate::error::ApiError;
+use crate::{
+    error::ApiError,
+    modules::audit::{self, AuditEntity},
+    utils::etag::Precondition,
+};
 
-use super::{port::DBRepository, Tenant};
+use super::{port::DBRepository, Tenant, TenantPatch};
 
 pub struct Service {
     db_port: Arc<dyn DBRepository>,
+    audit_service: Arc<audit::Service>,
 }
 impl Service {
-    pub fn new(db_port: Arc<dyn DBRepository>) -> Self {
-        Self { db_port }
+    pub fn new(db_port: Arc<dyn DBRepository>, audit_service: Arc<audit::Service>) -> Self {
+        Self {
+            db_port,
+            audit_service,
+        }
     }
 
     pub async fn find_by_user_id(&self, id: &str) -> Result<Tenant, ApiError> {
         self.db_port.find_by_user_id(id).await
     }
 
-    pub async fn update_tenant(&self, tenant: Tenant) -> Result<Tenant, ApiError> {
-        self.db_port.update(tenant).await
+    pub async fn update_tenant(
+        &self,
+        tenant: Tenant,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Tenant, ApiError> {
+        let before = self.db_port.find_by_id(tenant.id).await?;
+        let updated_tenant = self.db_port.update(tenant, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                updated_tenant.id,
+                AuditEntity::Tenant,
+                updated_tenant.id,
+                &before,
+                &updated_tenant,
+            )
+            .await;
+
+        Ok(updated_tenant)
+    }
+
+    pub async fn patch_tenant(
+        &self,
+        id: i32,
+        patch: TenantPatch,
+        precondition: &Precondition,
+        actor_user_id: &str,
+    ) -> Result<Tenant, ApiError> {
+        let before = self.db_port.find_by_id(id).await?;
+        let patched_tenant = self.db_port.patch(id, patch, precondition).await?;
+        self.audit_service
+            .record_update(
+                actor_user_id,
+                id,
+                AuditEntity::Tenant,
+                id,
+                &before,
+                &patched_tenant,
+            )
+            .await;
+
+        Ok(patched_tenant)
     }
 }
@@ -0,0 +1,38 @@
+use actix_web::HttpRequest;
+
+/// Finds the address of the client behind a known number of reverse proxies.
+///
+/// Each proxy appends the address it was connected from to `X-Forwarded-For`,
+/// so only the entries added by our own proxies can be trusted: anything to
+/// their left was written by the client.
+pub struct ClientIp {
+    trusted_proxies: usize,
+}
+
+impl ClientIp {
+    pub fn new(trusted_proxies: usize) -> Self {
+        Self { trusted_proxies }
+    }
+
+    pub fn resolve(&self, req: &HttpRequest) -> Option<String> {
+        let peer = || req.peer_addr().map(|addr| addr.ip().to_string());
+        if self.trusted_proxies == 0 {
+            return peer();
+        }
+
+        let hops: Vec<&str> = req
+            .headers()
+            .get_all("X-Forwarded-For")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|forwarded| forwarded.split(','))
+            .map(str::trim)
+            .collect();
+
+        // With fewer hops than proxies the request did not come through all of
+        // them, so the header is not ours.
+        match hops.len().checked_sub(self.trusted_proxies) {
+            Some(client) => Some(hops[client].to_string()).filter(|ip| !ip.is_empty()),
+            None => peer(),
+        }
+    }
+}
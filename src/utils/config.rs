@@ -4,6 +4,22 @@ pub struct Config {
     pub database_url: String,
     pub aws_region: String,
     pub s3_bucket: String,
+    pub trash_retention_days: i64,
+    /// Days raw stats events are kept once rolled up.
+    pub stats_retention_days: i64,
+    /// Reverse proxies in front of the API, whose `X-Forwarded-For` hops are
+    /// trusted to find the client address. `0` uses the peer address.
+    pub trusted_proxies: usize,
+    /// Public URL of a tenant's landing, with `{tenant_id}` as placeholder.
+    /// Sitemaps and robots.txt are unavailable without it.
+    pub site_url_template: Option<String>,
+    /// Public URL of this API, for links in emails.
+    pub public_api_url: Option<String>,
+    /// Endpoint of the transactional email API; emails are only logged without it.
+    pub mailer_url: Option<String>,
+    pub mailer_api_key: Option<String>,
+    /// Sender of the emails, e.g. `Vendy <reports@example.com>`.
+    pub mailer_from: Option<String>,
 }
 
 impl Config {
@@ -12,6 +28,23 @@ impl Config {
             database_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
             aws_region: std::env::var("AWS_REGION").expect("AWS_REGION must be set"),
             s3_bucket: std::env::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+            trash_retention_days: std::env::var("TRASH_RETENTION_DAYS")
+                .ok()
+                .and_then(|days| days.parse().ok())
+                .unwrap_or(30),
+            stats_retention_days: std::env::var("STATS_RETENTION_DAYS")
+                .ok()
+                .and_then(|days| days.parse().ok())
+                .unwrap_or(90),
+            trusted_proxies: std::env::var("TRUSTED_PROXIES")
+                .ok()
+                .and_then(|proxies| proxies.parse().ok())
+                .unwrap_or(1),
+            site_url_template: std::env::var("SITE_URL_TEMPLATE").ok(),
+            public_api_url: std::env::var("PUBLIC_API_URL").ok(),
+            mailer_url: std::env::var("MAILER_URL").ok(),
+            mailer_api_key: std::env::var("MAILER_API_KEY").ok(),
+            mailer_from: std::env::var("MAILER_FROM").ok(),
         }
     }
 }
@@ -99,6 +99,8 @@ pub enum FilterCondition {
     Like(String),
     JsonContains(String, Value),
     JsonExists(String),
+    IsNull,
+    IsNotNull,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -181,6 +183,12 @@ impl Filter {
                 FilterCondition::JsonExists(path) => {
                     conditions.push(format!("{}->>{} IS NOT NULL", field, path));
                 }
+                FilterCondition::IsNull => {
+                    conditions.push(format!("{} IS NULL", field));
+                }
+                FilterCondition::IsNotNull => {
+                    conditions.push(format!("{} IS NOT NULL", field));
+                }
             }
         }
 
@@ -1,8 +1,16 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 
-use sqlx::PgPool;
+use futures::stream::{self, BoxStream, StreamExt};
+use sqlx::{
+    postgres::PgRow, query_builder::Separated, Encode, PgPool, Postgres, Transaction, Type,
+};
 
-use crate::utils::Config;
+use crate::{error::ApiError, utils::Config};
+
+use super::Value;
+
+/// Rows pulled from the server per `FETCH` while streaming a cursor.
+const CURSOR_BATCH_SIZE: usize = 200;
 
 #[derive(Debug, Clone)]
 pub struct PostgresRepository {
@@ -23,3 +31,102 @@ impl PostgresRepository {
         }
     }
 }
+
+/// Appends `column = $n` to an `UPDATE ... SET` list when a patch supplied a value.
+pub fn push_set<'args, T>(
+    set: &mut Separated<'_, 'args, Postgres, &'static str>,
+    column: &str,
+    value: Option<T>,
+) where
+    T: 'args + Encode<'args, Postgres> + Send + Type<Postgres>,
+{
+    if let Some(value) = value {
+        set.push(format!("{} = ", column));
+        set.push_bind_unseparated(value);
+    }
+}
+
+enum CursorState {
+    Pending(Arc<PgPool>, String, Vec<Value>),
+    Open(Box<Transaction<'static, Postgres>>, VecDeque<PgRow>, bool),
+    Done,
+}
+
+/// Streams the rows of `query` through a server-side cursor, fetching them in
+/// batches so only one batch is held in memory at a time.
+pub fn cursor_stream<T, F>(
+    pg_pool: Arc<PgPool>,
+    query: String,
+    args: Vec<Value>,
+    map_row: F,
+) -> BoxStream<'static, Result<T, ApiError>>
+where
+    T: Send + 'static,
+    F: Fn(&PgRow) -> Result<T, ApiError> + Send + 'static,
+{
+    stream::unfold(CursorState::Pending(pg_pool, query, args), next_cursor_row)
+        .map(move |row| row.and_then(|row| map_row(&row)))
+        .boxed()
+}
+
+async fn next_cursor_row(mut state: CursorState) -> Option<(Result<PgRow, ApiError>, CursorState)> {
+    loop {
+        state = match state {
+            CursorState::Pending(pg_pool, query, args) => {
+                match open_cursor(&pg_pool, &query, args).await {
+                    Ok(tx) => CursorState::Open(Box::new(tx), VecDeque::new(), false),
+                    Err(e) => return Some((Err(e), CursorState::Done)),
+                }
+            }
+            CursorState::Open(tx, mut rows, exhausted) => {
+                if let Some(row) = rows.pop_front() {
+                    return Some((Ok(row), CursorState::Open(tx, rows, exhausted)));
+                }
+                if exhausted {
+                    // Read-only work, so ending the transaction by dropping it is enough.
+                    return None;
+                }
+
+                let mut tx = tx;
+                let batch = sqlx::query(&format!("FETCH {} FROM export_cursor", CURSOR_BATCH_SIZE))
+                    .fetch_all(&mut **tx)
+                    .await;
+
+                match batch {
+                    Ok(batch) => {
+                        let exhausted = batch.len() < CURSOR_BATCH_SIZE;
+                        CursorState::Open(tx, batch.into(), exhausted)
+                    }
+                    Err(e) => return Some((Err(ApiError::DatabaseError(e)), CursorState::Done)),
+                }
+            }
+            CursorState::Done => return None,
+        }
+    }
+}
+
+async fn open_cursor(
+    pg_pool: &PgPool,
+    query: &str,
+    args: Vec<Value>,
+) -> Result<Transaction<'static, Postgres>, ApiError> {
+    let mut tx = pg_pool.begin().await.map_err(ApiError::DatabaseError)?;
+
+    let declare = format!("DECLARE export_cursor NO SCROLL CURSOR FOR {}", query);
+    let mut declare_query = sqlx::query(&declare);
+    for arg in args {
+        declare_query = match arg {
+            Value::Int(i) => declare_query.bind(i),
+            Value::Float(f) => declare_query.bind(f),
+            Value::String(s) => declare_query.bind(s),
+            Value::Bool(b) => declare_query.bind(b),
+            Value::Json(j) => declare_query.bind(j),
+        };
+    }
+    declare_query
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::DatabaseError)?;
+
+    Ok(tx)
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use actix_web::{
+    http::header::{self, CacheControl, CacheDirective, EntityTag, Header, IfMatch, IfNoneMatch},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+
+use crate::error::ApiError;
+
+/// Strong ETag derived from a row's `updated_at`.
+pub fn entity_tag(updated_at: Option<DateTime<Utc>>) -> Option<EntityTag> {
+    updated_at.map(|updated_at| EntityTag::new_strong(updated_at.timestamp_micros().to_string()))
+}
+
+/// Version(s) a client expects a resource to be at, taken from `If-Match`.
+#[derive(Debug, Clone)]
+pub enum Precondition {
+    Any,
+    Versions(Vec<DateTime<Utc>>),
+}
+
+impl Precondition {
+    /// Reads `If-Match`, which every mutating request must send.
+    pub fn from_request(req: &HttpRequest) -> Result<Self, ApiError> {
+        if !req.headers().contains_key(header::IF_MATCH) {
+            return Err(ApiError::PreconditionRequired(
+                "Missing If-Match header".into(),
+            ));
+        }
+
+        match IfMatch::parse(req) {
+            Ok(IfMatch::Any) => Ok(Precondition::Any),
+            Ok(IfMatch::Items(tags)) => Ok(Precondition::Versions(
+                tags.iter()
+                    .filter(|tag| !tag.weak)
+                    .filter_map(|tag| tag.tag().parse::<i64>().ok())
+                    .filter_map(DateTime::from_timestamp_micros)
+                    .collect(),
+            )),
+            Err(_) => Err(ApiError::BadRequest("Malformed If-Match header".into())),
+        }
+    }
+
+    /// Fails with 412 when `current` is not one of the expected versions.
+    pub fn ensure(&self, current: Option<DateTime<Utc>>) -> Result<(), ApiError> {
+        match self {
+            Precondition::Any => Ok(()),
+            Precondition::Versions(versions) => match current {
+                Some(current) if versions.contains(&current) => Ok(()),
+                _ => Err(ApiError::PreconditionFailed(
+                    "Resource was modified by another request".into(),
+                )),
+            },
+        }
+    }
+}
+
+/// Publicly cacheable `200` tagged with a hash of `body`, or `304 Not Modified`
+/// when the client already holds it according to `If-None-Match`.
+pub fn conditional_response(
+    req: &HttpRequest,
+    body: Vec<u8>,
+    content_type: &str,
+    max_age_secs: u32,
+) -> HttpResponse {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = EntityTag::new_strong(format!("{:x}", hasher.finish()));
+
+    let not_modified = match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        Err(_) => false,
+    };
+
+    let cache_control = CacheControl(vec![
+        CacheDirective::Public,
+        CacheDirective::MaxAge(max_age_secs),
+    ]);
+
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .insert_header(cache_control)
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header(header::ETag(etag))
+        .insert_header(cache_control)
+        .content_type(content_type)
+        .body(body)
+}
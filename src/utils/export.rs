@@ -0,0 +1,153 @@
+use actix_web::{
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::Bytes,
+    HttpResponse,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Flat representation of a record for CSV exports.
+pub trait CsvRecord {
+    fn csv_header() -> &'static [&'static str];
+    fn csv_record(&self) -> Vec<String>;
+}
+
+/// Streams `items` as a downloadable `filename.<csv|jsonl>` attachment.
+pub fn export_response<T>(
+    items: BoxStream<'static, Result<T, ApiError>>,
+    format: ExportFormat,
+    filename: &str,
+) -> HttpResponse
+where
+    T: Serialize + CsvRecord + Send + 'static,
+{
+    let header = match format {
+        ExportFormat::Csv => Some(csv_line(T::csv_header())),
+        ExportFormat::Jsonl => None,
+    };
+
+    let lines = items.map(move |item| {
+        item.and_then(|item| match format {
+            ExportFormat::Csv => csv_line(&item.csv_record()),
+            ExportFormat::Jsonl => jsonl_line(&item),
+        })
+        .inspect_err(|e| log::error!("Export aborted: {}", e))
+    });
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "{}.{}",
+                filename,
+                format.extension()
+            ))],
+        })
+        .streaming(stream::iter(header).chain(lines))
+}
+
+fn csv_line<S: AsRef<str>>(fields: &[S]) -> Result<Bytes, ApiError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(
+            fields
+                .iter()
+                .map(|field| neutralize_formula(field.as_ref())),
+        )
+        .map_err(|e| ApiError::UnexpectedError(e.to_string()))?;
+    let line = writer
+        .into_inner()
+        .map_err(|e| ApiError::UnexpectedError(e.to_string()))?;
+    Ok(Bytes::from(line))
+}
+
+/// Spreadsheets run cells starting with these as formulas, and exports carry
+/// text sent by anonymous visitors, such as referrers and campaigns.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quotes fields a spreadsheet would evaluate, leaving negative numbers as they are.
+fn neutralize_formula(field: &str) -> String {
+    if field.starts_with(FORMULA_PREFIXES) && field.parse::<f64>().is_err() {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    }
+}
+
+/// Undoes `neutralize_formula`, so an exported file can be imported back as is.
+pub fn restore_formula(field: &str) -> &str {
+    match field.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_PREFIXES) && rest.parse::<f64>().is_err() => rest,
+        _ => field,
+    }
+}
+
+fn jsonl_line<T: Serialize>(item: &T) -> Result<Bytes, ApiError> {
+    let mut line = serde_json::to_vec(item)?;
+    line.push(b'\n');
+    Ok(Bytes::from(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formulas_are_quoted_and_numbers_kept() {
+        assert_eq!(
+            neutralize_formula("=HYPERLINK(\"x\")"),
+            "'=HYPERLINK(\"x\")"
+        );
+        assert_eq!(neutralize_formula("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(neutralize_formula("-1500.5"), "-1500.5");
+        assert_eq!(neutralize_formula("Casa en Lima"), "Casa en Lima");
+    }
+
+    #[test]
+    fn restoring_a_neutralized_field_gives_it_back() {
+        for field in [
+            "=1+1",
+            "+51 999 999 999",
+            "- Terraza",
+            "@home",
+            "-3",
+            "Casa",
+            "'quoted",
+        ] {
+            assert_eq!(restore_formula(&neutralize_formula(field)), field);
+        }
+    }
+}
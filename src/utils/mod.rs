@@ -1,7 +1,17 @@
 mod config;
 pub use config::*;
 
+pub mod client_ip;
 pub mod database;
+pub mod etag;
+pub mod export;
 pub mod lucia;
+pub mod patch;
+pub mod rate_limit;
+pub mod request_id;
 
 pub mod s3;
+pub mod slug;
+pub mod user_agent;
+pub mod validation;
+pub mod xml;
@@ -0,0 +1,24 @@
+//! Serde helpers for JSON merge patch (RFC 7396) request bodies.
+//!
+//! Patch fields are declared with `#[serde(default, deserialize_with = ...)]`
+//! so a missing member stays `None` and means "leave unchanged".
+
+use serde::{Deserialize, Deserializer};
+
+/// For nullable columns: `null` clears the column (`Some(None)`).
+pub fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// For columns that cannot be cleared: `null` is rejected.
+pub fn non_null<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
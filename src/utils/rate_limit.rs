@@ -0,0 +1,72 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::error::ApiError;
+
+/// Keys tracked at once. Past it the oldest windows are dropped, so a flood
+/// of distinct keys costs bounded memory.
+const MAX_KEYS: usize = 100_000;
+
+/// In-memory fixed-window counter of hits per key (e.g. per client IP).
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    windows: Mutex<Windows>,
+}
+
+#[derive(Default)]
+struct Windows {
+    hits: HashMap<String, (Instant, u32)>,
+    /// Windows in the order they started. Entries whose key has started a
+    /// newer window since are skipped when popped.
+    started: VecDeque<(String, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            windows: Mutex::new(Windows::default()),
+        }
+    }
+
+    /// Counts a hit for `key`, failing once it goes over the limit of the current window.
+    pub fn check(&self, key: &str) -> Result<(), ApiError> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let Windows { hits, started } = &mut *windows;
+
+        // Oldest first, so this stops at the first window still running.
+        while let Some((oldest, started_at)) = started.front() {
+            if now.duration_since(*started_at) < self.window && started.len() < MAX_KEYS {
+                break;
+            }
+            if hits
+                .get(oldest)
+                .is_some_and(|(current, _)| current == started_at)
+            {
+                hits.remove(oldest);
+            }
+            started.pop_front();
+        }
+
+        let (started_at, count) = hits.entry(key.to_string()).or_insert((now, 0));
+        if *count == 0 || now.duration_since(*started_at) >= self.window {
+            *started_at = now;
+            *count = 0;
+            started.push_back((key.to_string(), now));
+        }
+
+        *count += 1;
+        if *count > self.limit {
+            let retry_after = self.window.saturating_sub(now.duration_since(*started_at));
+            return Err(ApiError::RateLimited(retry_after.as_secs().max(1)));
+        }
+
+        Ok(())
+    }
+}
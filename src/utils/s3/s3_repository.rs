@@ -3,8 +3,10 @@ use aws_sdk_s3::config::{BehaviorVersion, Region};
 use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::types::ObjectCannedAcl;
 use aws_sdk_s3::Client as S3Client;
+use futures::future::join_all;
 use std::sync::Arc;
 use std::time::Duration;
+use url::Url;
 
 use crate::error::ApiError;
 use crate::utils::Config;
@@ -13,6 +15,7 @@ use crate::utils::Config;
 pub struct S3Repository {
     client: Arc<S3Client>,
     bucket: String,
+    region: String,
 }
 
 impl S3Repository {
@@ -31,11 +34,16 @@ impl S3Repository {
             })?)
             .build();
 
+        let region = s3_config
+            .region()
+            .map(|region| region.to_string())
+            .unwrap_or_else(|| config.aws_region.clone());
         let client = S3Client::from_conf(s3_config);
 
         Ok(Self {
             client: Arc::new(client),
             bucket: config.s3_bucket,
+            region,
         })
     }
 
@@ -60,6 +68,63 @@ impl S3Repository {
         Ok(presigned_req.uri().to_string())
     }
 
+    /// Uploads a publicly readable object and returns its URL.
+    pub async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, ApiError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .acl(ObjectCannedAcl::PublicRead)
+            .content_type(content_type)
+            .body(body.into())
+            .send()
+            .await
+            .map_err(|e| ApiError::UnexpectedError(e.to_string()))?;
+
+        Ok(format!(
+            "https://{}.s3.{}.amazonaws.com/{}",
+            self.bucket, self.region, key
+        ))
+    }
+
+    /// Key of an object given the key itself or its URL in this bucket, as
+    /// returned by `put_object` or a presigned upload. `None` for URLs of
+    /// anything else, which there is nothing to delete from.
+    fn object_key(&self, image: &str) -> Option<String> {
+        let Ok(url) = Url::parse(image) else {
+            return Some(image.to_string());
+        };
+        let host = url.host_str()?;
+        let path = url.path().strip_prefix('/').unwrap_or(url.path());
+
+        if host.starts_with(&format!("{}.s3.", self.bucket)) {
+            Some(path.to_string())
+        } else if host.starts_with("s3.") && host.ends_with(".amazonaws.com") {
+            // Path-style URL, the bucket being the first segment
+            path.strip_prefix(&format!("{}/", self.bucket))
+                .map(str::to_string)
+        } else {
+            None
+        }
+    }
+
+    /// Deletes the objects given by key or URL, see `object_key`, returning
+    /// the ones deleted.
+    pub async fn delete_objects(&self, images: &[String]) -> Result<Vec<String>, ApiError> {
+        let delete_futures = images.iter().filter_map(|image| {
+            let key = self.object_key(image)?;
+            let image = image.clone();
+            Some(async move { self.delete_object(&key).await.map(|_| image) })
+        });
+
+        join_all(delete_futures).await.into_iter().collect()
+    }
+
     pub async fn delete_object(&self, key: &str) -> Result<(), ApiError> {
         self.client
             .delete_object()
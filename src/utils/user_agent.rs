@@ -0,0 +1,109 @@
+/// Lowercase fragments of the user agents of crawlers, link previewers,
+/// uptime monitors and HTTP libraries. Keep in sync with the backfill in
+/// `migrations/009_stats_bot_traffic.up.sql`.
+const BOT_SIGNATURES: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "mediapartners",
+    "facebookexternalhit",
+    "embedly",
+    "preview",
+    "whatsapp",
+    "pingdom",
+    "uptimerobot",
+    "statuscake",
+    "site24x7",
+    "newrelicpinger",
+    "datadog",
+    "headlesschrome",
+    "phantomjs",
+    "lighthouse",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "java/",
+    "okhttp",
+    "axios/",
+    "node-fetch",
+    "postmanruntime",
+    "scrapy",
+    "semrush",
+    "ahrefs",
+    "mj12",
+];
+
+/// Coarse device class of a browser, judged from its `User-Agent`.
+pub fn device_type(user_agent: &str) -> &'static str {
+    let user_agent = user_agent.to_ascii_lowercase();
+
+    if user_agent.contains("ipad")
+        || user_agent.contains("tablet")
+        || (user_agent.contains("android") && !user_agent.contains("mobile"))
+    {
+        "tablet"
+    } else if user_agent.contains("mobi")
+        || user_agent.contains("iphone")
+        || user_agent.contains("android")
+    {
+        "mobile"
+    } else {
+        "desktop"
+    }
+}
+
+/// Whether `user_agent` matches a known bot signature.
+pub fn is_bot(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_ascii_lowercase();
+    BOT_SIGNATURES
+        .iter()
+        .any(|signature| user_agent.contains(signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browsers_are_not_bots() {
+        let browsers = [
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+            "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+            "Mozilla/5.0 (Linux; Android 14; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/24.0 Chrome/117.0.0.0 Mobile Safari/537.36",
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0",
+        ];
+
+        for browser in browsers {
+            assert!(!is_bot(browser), "{}", browser);
+        }
+    }
+
+    #[test]
+    fn crawlers_previewers_and_libraries_are_bots() {
+        let bots = [
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "WhatsApp/2.23.20.0",
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/124.0.0.0 Safari/537.36",
+            "curl/8.4.0",
+            "python-requests/2.31.0",
+            "Go-http-client/1.1",
+            "Mozilla/5.0 (compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)",
+        ];
+
+        for bot in bots {
+            assert!(is_bot(bot), "{}", bot);
+        }
+    }
+
+    #[test]
+    fn signatures_match_regardless_of_case() {
+        assert!(is_bot("SEMRUSHBOT"));
+        assert!(is_bot("Scrapy/2.11 (+https://scrapy.org)"));
+    }
+}
@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+
+use url::Url;
+use validator::ValidationError;
+
+/// `#rgb` or `#rrggbb`, the only shapes that fit `config.color VARCHAR(7)`.
+pub fn validate_hex_color(color: &str) -> Result<(), ValidationError> {
+    let digits = color.strip_prefix('#').unwrap_or_default();
+    let valid = color.starts_with('#')
+        && matches!(digits.len(), 3 | 6)
+        && digits.chars().all(|c| c.is_ascii_hexdigit());
+
+    if valid {
+        Ok(())
+    } else {
+        Err(error("hex_color", "must be a hex color like #1a2b3c"))
+    }
+}
+
+/// Accepts `http(s)` URLs whose host is `domain` or one of its subdomains.
+pub fn validate_url_domain(url: &str, domain: &str) -> Result<(), ValidationError> {
+    // Parsed as browsers do, so e.g. `https://evil.com\.facebook.com` has host `evil.com`
+    let host = Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .and_then(|url| url.host_str().map(str::to_string));
+
+    match host {
+        Some(host) if host == domain || host.ends_with(&format!(".{}", domain)) => Ok(()),
+        _ => Err(error("url_domain", &format!("must be a {} URL", domain))),
+    }
+}
+
+/// Absolute URL of any scheme, as browsers report pages and referrers (e.g.
+/// `android-app://`), or empty when there is none.
+pub fn validate_page_url(url: &str) -> Result<(), ValidationError> {
+    if url.is_empty() || Url::parse(url).is_ok() {
+        Ok(())
+    } else {
+        Err(error("url", "must be an absolute URL"))
+    }
+}
+
+/// Lowercase ASCII words joined by single dashes, as produced by `slugify`.
+pub fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    let valid = !slug.is_empty()
+        && slug.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(error(
+            "slug",
+            "must be lowercase letters and digits separated by dashes",
+        ))
+    }
+}
+
+/// Rejects `NaN` and infinities, which parse from text and slip past `range`.
+pub fn validate_finite(value: f64) -> Result<(), ValidationError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(error("finite", "must be a finite number"))
+    }
+}
+
+fn error(code: &'static str, message: &str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Owned(message.to_string()));
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_domain_accepts_the_domain_and_its_subdomains() {
+        assert!(validate_url_domain("https://facebook.com/page", "facebook.com").is_ok());
+        assert!(validate_url_domain("http://www.Facebook.com?x=1", "facebook.com").is_ok());
+        assert!(validate_url_domain("https://m.facebook.com:443/page", "facebook.com").is_ok());
+        assert!(validate_url_domain("https://facebook.com:8443/page", "facebook.com").is_ok());
+    }
+
+    #[test]
+    fn url_domain_rejects_other_hosts() {
+        assert!(validate_url_domain("https://evil.com\\.facebook.com", "facebook.com").is_err());
+        assert!(validate_url_domain("https://facebook.com.evil.com/", "facebook.com").is_err());
+        assert!(validate_url_domain("https://notfacebook.com/", "facebook.com").is_err());
+        assert!(validate_url_domain("https://facebook.com@evil.com/", "facebook.com").is_err());
+        assert!(validate_url_domain("ftp://facebook.com/", "facebook.com").is_err());
+        assert!(validate_url_domain("facebook.com/page", "facebook.com").is_err());
+    }
+}